@@ -1,5 +1,5 @@
 use criterion::{criterion_group, criterion_main, BatchSize, Criterion, SamplingMode};
-use rust_project_fiit_stu::{dll, immutable_ll, immutable_thread_safe_ll, ll};
+use rust_project_fiit_stu::{dll, immutable_ll, immutable_thread_safe_ll, ll, weak_dll};
 
 criterion_group!(big_data_ops, bench_push, bench_pop, bench_get);
 criterion_main!(big_data_ops);
@@ -12,16 +12,16 @@ fn bench_push(c: &mut Criterion) {
     group.sampling_mode(SamplingMode::Flat);
 
     let mut big_arr: [u64; M] = [0; M];
-    for i in 0..M {
-        big_arr[i] = i as u64;
+    for (i, val) in big_arr.iter_mut().enumerate() {
+        *val = i as u64;
     }
 
     group.bench_function("Vec", |b| {
         b.iter_batched(
-            || Vec::new(),
+            Vec::new,
             |mut v| {
                 for _ in 0..N {
-                    v.push(big_arr.clone());
+                    v.push(big_arr);
                 }
             },
             BatchSize::SmallInput,
@@ -30,10 +30,10 @@ fn bench_push(c: &mut Criterion) {
 
     group.bench_function("Std LL", |b| {
         b.iter_batched(
-            || std::collections::LinkedList::new(),
+            std::collections::LinkedList::new,
             |mut std_ll| {
                 for _ in 0..N {
-                    std_ll.push_front(big_arr.clone());
+                    std_ll.push_front(big_arr);
                 }
             },
             BatchSize::SmallInput,
@@ -42,10 +42,10 @@ fn bench_push(c: &mut Criterion) {
 
     group.bench_function("LL", |b| {
         b.iter_batched(
-            || ll::LinkedList::new(),
+            ll::LinkedList::new,
             |mut ll| {
                 for _ in 0..N {
-                    ll.push(big_arr.clone());
+                    ll.push(big_arr);
                 }
             },
             BatchSize::SmallInput,
@@ -54,10 +54,10 @@ fn bench_push(c: &mut Criterion) {
 
     group.bench_function("Immutable LL", |b| {
         b.iter_batched(
-            || immutable_ll::ImmutableLinkedList::new(),
+            immutable_ll::ImmutableLinkedList::new,
             |mut immutable_ll| {
                 for _ in 0..N {
-                    immutable_ll = immutable_ll.prepend(big_arr.clone());
+                    immutable_ll = immutable_ll.prepend(big_arr);
                 }
             },
             BatchSize::SmallInput,
@@ -66,10 +66,58 @@ fn bench_push(c: &mut Criterion) {
 
     group.bench_function("Thread Safe LL", |b| {
         b.iter_batched(
-            || immutable_thread_safe_ll::ImmutableLinkedList::new(),
+            immutable_thread_safe_ll::ImmutableLinkedList::new,
             |mut immutable_thread_safe_ll| {
                 for _ in 0..N {
-                    immutable_thread_safe_ll = immutable_thread_safe_ll.prepend(big_arr.clone());
+                    immutable_thread_safe_ll = immutable_thread_safe_ll.prepend(big_arr);
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Immutable Queue", |b| {
+        b.iter_batched(
+            immutable_ll::ImmutableQueue::new,
+            |mut immutable_queue| {
+                for _ in 0..N {
+                    immutable_queue = immutable_queue.push_back(big_arr);
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Thread Safe Queue", |b| {
+        b.iter_batched(
+            immutable_thread_safe_ll::ImmutableQueue::new,
+            |mut thread_safe_queue| {
+                for _ in 0..N {
+                    thread_safe_queue = thread_safe_queue.push_back(big_arr);
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Immutable Deque", |b| {
+        b.iter_batched(
+            immutable_ll::ImmutableDeque::new,
+            |mut immutable_deque| {
+                for _ in 0..N {
+                    immutable_deque = immutable_deque.push_front(big_arr);
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Thread Safe Deque", |b| {
+        b.iter_batched(
+            immutable_thread_safe_ll::ImmutableDeque::new,
+            |mut thread_safe_deque| {
+                for _ in 0..N {
+                    thread_safe_deque = thread_safe_deque.push_front(big_arr);
                 }
             },
             BatchSize::SmallInput,
@@ -78,10 +126,22 @@ fn bench_push(c: &mut Criterion) {
 
     group.bench_function("DLL", |b| {
         b.iter_batched(
-            || dll::DoublyLinkedList::new(),
+            dll::DoublyLinkedList::new,
+            |mut dll| {
+                for _ in 0..N {
+                    dll.push_front(big_arr);
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Weak DLL", |b| {
+        b.iter_batched(
+            weak_dll::DoublyLinkedList::new,
             |mut dll| {
                 for _ in 0..N {
-                    dll.push_front(big_arr.clone());
+                    dll.push_front(big_arr);
                 }
             },
             BatchSize::SmallInput,
@@ -94,8 +154,8 @@ fn bench_pop(c: &mut Criterion) {
     group.sampling_mode(SamplingMode::Flat);
 
     let mut big_arr: [u64; M] = [0; M];
-    for i in 0..M {
-        big_arr[i] = i as u64;
+    for (i, val) in big_arr.iter_mut().enumerate() {
+        *val = i as u64;
     }
 
     let mut blackhole: Option<[u64; M]> = None;
@@ -104,7 +164,7 @@ fn bench_pop(c: &mut Criterion) {
             move || {
                 let mut v = Vec::new();
                 for _ in 0..N {
-                    v.push(big_arr.clone());
+                    v.push(big_arr);
                 }
                 v
             },
@@ -122,7 +182,7 @@ fn bench_pop(c: &mut Criterion) {
             || {
                 let mut std_ll = std::collections::LinkedList::new();
                 for _ in 0..N {
-                    std_ll.push_front(big_arr.clone());
+                    std_ll.push_front(big_arr);
                 }
                 std_ll
             },
@@ -140,7 +200,7 @@ fn bench_pop(c: &mut Criterion) {
             || {
                 let mut ll = ll::LinkedList::new();
                 for _ in 0..N {
-                    ll.push(big_arr.clone());
+                    ll.push(big_arr);
                 }
                 ll
             },
@@ -158,7 +218,7 @@ fn bench_pop(c: &mut Criterion) {
             || {
                 let mut immutable_ll = immutable_ll::ImmutableLinkedList::new();
                 for _ in 0..N {
-                    immutable_ll = immutable_ll.prepend(big_arr.clone());
+                    immutable_ll = immutable_ll.prepend(big_arr);
                 }
                 immutable_ll
             },
@@ -177,7 +237,7 @@ fn bench_pop(c: &mut Criterion) {
                 let mut immutable_thread_safe_ll =
                     immutable_thread_safe_ll::ImmutableLinkedList::new();
                 for _ in 0..N {
-                    immutable_thread_safe_ll = immutable_thread_safe_ll.prepend(big_arr.clone());
+                    immutable_thread_safe_ll = immutable_thread_safe_ll.prepend(big_arr);
                 }
                 immutable_thread_safe_ll
             },
@@ -190,12 +250,102 @@ fn bench_pop(c: &mut Criterion) {
         );
     });
 
+    group.bench_function("Immutable Queue", |b| {
+        b.iter_batched(
+            || {
+                let mut immutable_queue = immutable_ll::ImmutableQueue::new();
+                for _ in 0..N {
+                    immutable_queue = immutable_queue.push_back(big_arr);
+                }
+                immutable_queue
+            },
+            |mut immutable_queue| {
+                for _ in 0..N {
+                    immutable_queue = immutable_queue.pop_front();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Thread Safe Queue", |b| {
+        b.iter_batched(
+            || {
+                let mut thread_safe_queue = immutable_thread_safe_ll::ImmutableQueue::new();
+                for _ in 0..N {
+                    thread_safe_queue = thread_safe_queue.push_back(big_arr);
+                }
+                thread_safe_queue
+            },
+            |mut thread_safe_queue| {
+                for _ in 0..N {
+                    thread_safe_queue = thread_safe_queue.pop_front();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Immutable Deque", |b| {
+        b.iter_batched(
+            || {
+                let mut immutable_deque = immutable_ll::ImmutableDeque::new();
+                for _ in 0..N {
+                    immutable_deque = immutable_deque.push_front(big_arr);
+                }
+                immutable_deque
+            },
+            |mut immutable_deque| {
+                for _ in 0..N {
+                    immutable_deque = immutable_deque.pop_front();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Thread Safe Deque", |b| {
+        b.iter_batched(
+            || {
+                let mut thread_safe_deque = immutable_thread_safe_ll::ImmutableDeque::new();
+                for _ in 0..N {
+                    thread_safe_deque = thread_safe_deque.push_front(big_arr);
+                }
+                thread_safe_deque
+            },
+            |mut thread_safe_deque| {
+                for _ in 0..N {
+                    thread_safe_deque = thread_safe_deque.pop_front();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
     group.bench_function("DLL", |b| {
         b.iter_batched(
             || {
                 let mut dll = dll::DoublyLinkedList::new();
                 for _ in 0..N {
-                    dll.push_front(big_arr.clone());
+                    dll.push_front(big_arr);
+                }
+                dll
+            },
+            |mut dll| {
+                for _ in 0..N {
+                    blackhole = dll.pop_front();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Weak DLL", |b| {
+        b.iter_batched(
+            || {
+                let mut dll = weak_dll::DoublyLinkedList::new();
+                for _ in 0..N {
+                    dll.push_front(big_arr);
                 }
                 dll
             },
@@ -214,14 +364,14 @@ fn bench_get(c: &mut Criterion) {
     group.sampling_mode(SamplingMode::Flat);
 
     let mut big_arr: [u64; M] = [0; M];
-    for i in 0..M {
-        big_arr[i] = i as u64;
+    for (i, val) in big_arr.iter_mut().enumerate() {
+        *val = i as u64;
     }
 
     group.bench_function("Vec", |b| {
         let mut v = Vec::new();
         for _ in 0..N {
-            v.push(big_arr.clone());
+            v.push(big_arr);
         }
 
         b.iter(|| v.get(N / 2))
@@ -232,7 +382,7 @@ fn bench_get(c: &mut Criterion) {
     group.bench_function("LL", |b| {
         let mut ll = ll::LinkedList::new();
         for _ in 0..N {
-            ll.push(big_arr.clone());
+            ll.push(big_arr);
         }
 
         b.iter(|| ll.get(N / 2))
@@ -241,7 +391,7 @@ fn bench_get(c: &mut Criterion) {
     group.bench_function("Immutable LL", |b| {
         let mut immutable_ll = immutable_ll::ImmutableLinkedList::new();
         for _ in 0..N {
-            immutable_ll = immutable_ll.prepend(big_arr.clone());
+            immutable_ll = immutable_ll.prepend(big_arr);
         }
 
         b.iter(|| immutable_ll.get(N / 2))
@@ -250,11 +400,21 @@ fn bench_get(c: &mut Criterion) {
     group.bench_function("Thread Safe LL", |b| {
         let mut immutable_thread_safe_ll = immutable_thread_safe_ll::ImmutableLinkedList::new();
         for _ in 0..N {
-            immutable_thread_safe_ll = immutable_thread_safe_ll.prepend(big_arr.clone());
+            immutable_thread_safe_ll = immutable_thread_safe_ll.prepend(big_arr);
         }
 
         b.iter(|| immutable_thread_safe_ll.get(N / 2))
     });
 
-    // DoublyLinkedList does not support indexing
+    // ImmutableQueue and ImmutableDeque only give access to their ends
+
+    // the middle is the furthest from both ends
+    group.bench_function("DLL", |b| {
+        let mut dll = dll::DoublyLinkedList::new();
+        for _ in 0..N {
+            dll.push_back(big_arr);
+        }
+
+        b.iter(|| dll.get(N / 2))
+    });
 }
@@ -0,0 +1,318 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+#[cfg(feature = "rayon")]
+use rayon::iter::ParallelIterator;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, SamplingMode};
+use rust_project_fiit_stu::{
+    atomic_list, immutable_ll, immutable_thread_safe_ll, ll, ms_queue, thread_safe_dll,
+    treiber_stack,
+};
+
+criterion_group!(
+    concurrent,
+    bench_stack,
+    bench_queue,
+    bench_deque,
+    bench_shared_persistent,
+    bench_par_iter
+);
+criterion_main!(concurrent);
+
+// total number of push + pop pairs, split evenly between the threads
+const N: usize = 100000;
+const THREADS: [usize; 4] = [1, 2, 4, 8];
+
+fn bench_stack(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Concurrent-Stack");
+    group.sampling_mode(SamplingMode::Flat);
+
+    for threads in THREADS {
+        group.bench_with_input(
+            BenchmarkId::new("Mutex LL", threads),
+            &threads,
+            |b, &threads| {
+                b.iter(|| {
+                    let ll = Mutex::new(ll::LinkedList::new());
+                    thread::scope(|s| {
+                        for _ in 0..threads {
+                            s.spawn(|| {
+                                for i in 0..N / threads {
+                                    ll.lock().unwrap().push(i);
+                                    ll.lock().unwrap().pop();
+                                }
+                            });
+                        }
+                    });
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("Treiber Stack", threads),
+            &threads,
+            |b, &threads| {
+                b.iter(|| {
+                    let stack = treiber_stack::TreiberStack::new();
+                    thread::scope(|s| {
+                        for _ in 0..threads {
+                            s.spawn(|| {
+                                for i in 0..N / threads {
+                                    stack.push(i);
+                                    stack.pop();
+                                }
+                            });
+                        }
+                    });
+                })
+            },
+        );
+    }
+}
+
+// (producers, consumers)
+const PRODUCERS_CONSUMERS: [(usize, usize); 5] = [(1, 1), (2, 2), (4, 4), (1, 4), (4, 1)];
+
+// producers push N elements in total, consumers pop until all of them are gone
+fn bench_queue(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Concurrent-Queue");
+    group.sampling_mode(SamplingMode::Flat);
+
+    for (producers, consumers) in PRODUCERS_CONSUMERS {
+        let id = format!("{}p{}c", producers, consumers);
+
+        group.bench_function(BenchmarkId::new("Mutex VecDeque", &id), |b| {
+            b.iter(|| {
+                let queue = Mutex::new(VecDeque::new());
+                let popped = AtomicUsize::new(0);
+                thread::scope(|s| {
+                    for _ in 0..producers {
+                        s.spawn(|| {
+                            for i in 0..N / producers {
+                                queue.lock().unwrap().push_back(i);
+                            }
+                        });
+                    }
+                    for _ in 0..consumers {
+                        s.spawn(|| {
+                            while popped.load(Ordering::Relaxed) < N / producers * producers {
+                                if queue.lock().unwrap().pop_front().is_some() {
+                                    popped.fetch_add(1, Ordering::Relaxed);
+                                }
+                            }
+                        });
+                    }
+                });
+            })
+        });
+
+        group.bench_function(BenchmarkId::new("MS Queue", &id), |b| {
+            b.iter(|| {
+                let queue = ms_queue::MsQueue::new();
+                let popped = AtomicUsize::new(0);
+                thread::scope(|s| {
+                    for _ in 0..producers {
+                        s.spawn(|| {
+                            for i in 0..N / producers {
+                                queue.push_back(i);
+                            }
+                        });
+                    }
+                    for _ in 0..consumers {
+                        s.spawn(|| {
+                            while popped.load(Ordering::Relaxed) < N / producers * producers {
+                                if queue.pop_front().is_some() {
+                                    popped.fetch_add(1, Ordering::Relaxed);
+                                }
+                            }
+                        });
+                    }
+                });
+            })
+        });
+    }
+}
+
+// every thread pushes and pops at both ends of the shared deque
+fn bench_deque(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Concurrent-Deque");
+    group.sampling_mode(SamplingMode::Flat);
+
+    for threads in THREADS {
+        group.bench_with_input(
+            BenchmarkId::new("Mutex VecDeque", threads),
+            &threads,
+            |b, &threads| {
+                b.iter(|| {
+                    let deque = Mutex::new(VecDeque::new());
+                    thread::scope(|s| {
+                        for _ in 0..threads {
+                            s.spawn(|| {
+                                for i in 0..N / threads {
+                                    deque.lock().unwrap().push_front(i);
+                                    deque.lock().unwrap().push_back(i);
+                                    deque.lock().unwrap().pop_front();
+                                    deque.lock().unwrap().pop_back();
+                                }
+                            });
+                        }
+                    });
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("Thread Safe DLL", threads),
+            &threads,
+            |b, &threads| {
+                b.iter(|| {
+                    let dll = thread_safe_dll::DoublyLinkedList::new();
+                    thread::scope(|s| {
+                        for _ in 0..threads {
+                            s.spawn(|| {
+                                for i in 0..N / threads {
+                                    dll.push_front(i);
+                                    dll.push_back(i);
+                                    dll.pop_front();
+                                    dll.pop_back();
+                                }
+                            });
+                        }
+                    });
+                })
+            },
+        );
+    }
+}
+
+// number of elements every thread prepends to its version of the shared list
+const PREPENDS: usize = 1000;
+
+// Every thread takes the shared list, traverses it and prepends to it. The
+// thread count is the input of the benchmark, so criterion reports how each
+// variant scales with the number of threads:
+// - Thread Safe LL, every thread clones the shared list in O(1) and prepends
+//   to its own version, paying only for the atomic refcounts
+// - Atomic List, all threads prepend to one shared version through rcu, so they
+//   see each other's elements and retry on conflicts
+// - Immutable LL, the Rc flavour can't be shared between threads, so it only
+//   has the single thread point, run on the bench thread itself, the baseline
+//   which shows what the atomic refcounts (and spawning the one thread) of
+//   Thread Safe LL cost
+// - VecDeque Copy, without persistence every thread has to copy the whole data,
+//   then prepends with push_front like the lists do
+fn bench_shared_persistent(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Concurrent-Shared Persistent List");
+    group.sampling_mode(SamplingMode::Flat);
+
+    let mut shared = immutable_thread_safe_ll::ImmutableLinkedList::new();
+    for i in 0..N {
+        shared = shared.prepend(i);
+    }
+    let shared_deque: VecDeque<usize> = (0..N).rev().collect();
+
+    let mut shared_rc = immutable_ll::ImmutableLinkedList::new();
+    for i in 0..N {
+        shared_rc = shared_rc.prepend(i);
+    }
+    group.bench_with_input(BenchmarkId::new("Immutable LL", 1), &1, |b, _| {
+        b.iter(|| {
+            let mut mine = shared_rc.clone();
+            black_box(mine.iter().sum::<usize>());
+            for i in 0..PREPENDS {
+                mine = mine.prepend(i);
+            }
+            mine
+        })
+    });
+
+    for threads in THREADS {
+        group.bench_with_input(
+            BenchmarkId::new("Thread Safe LL", threads),
+            &threads,
+            |b, &threads| {
+                b.iter(|| {
+                    thread::scope(|s| {
+                        for _ in 0..threads {
+                            s.spawn(|| {
+                                let mut mine = shared.clone();
+                                black_box(mine.iter().sum::<usize>());
+                                for i in 0..PREPENDS {
+                                    mine = mine.prepend(i);
+                                }
+                                mine
+                            });
+                        }
+                    });
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("Atomic List", threads),
+            &threads,
+            |b, &threads| {
+                b.iter(|| {
+                    let list = atomic_list::AtomicList::new(shared.clone());
+                    thread::scope(|s| {
+                        for _ in 0..threads {
+                            s.spawn(|| {
+                                black_box(list.load().iter().sum::<usize>());
+                                for i in 0..PREPENDS {
+                                    list.rcu(|old| old.prepend(i));
+                                }
+                            });
+                        }
+                    });
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("VecDeque Copy", threads),
+            &threads,
+            |b, &threads| {
+                b.iter(|| {
+                    thread::scope(|s| {
+                        for _ in 0..threads {
+                            s.spawn(|| {
+                                let mut mine = shared_deque.clone();
+                                black_box(mine.iter().sum::<usize>());
+                                for i in 0..PREPENDS {
+                                    mine.push_front(i);
+                                }
+                                mine
+                            });
+                        }
+                    });
+                })
+            },
+        );
+    }
+}
+
+// some CPU bound work per element, so there is something to parallelize
+fn work(val: &usize) -> usize {
+    (0..100).fold(*val, |acc, i| acc.wrapping_mul(31).wrapping_add(i)) % 1024
+}
+
+// par_iter is only parallel with the rayon feature enabled, without it this
+// compares the sequential fallback with itself
+fn bench_par_iter(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Concurrent-Par Iter");
+    group.sampling_mode(SamplingMode::Flat);
+
+    let mut list = immutable_thread_safe_ll::ImmutableLinkedList::new();
+    for i in 0..N {
+        list = list.prepend(i);
+    }
+
+    group.bench_function("Iter", |b| b.iter(|| list.iter().map(work).sum::<usize>()));
+
+    group.bench_function("Par Iter", |b| {
+        b.iter(|| list.par_iter().map(work).sum::<usize>())
+    });
+}
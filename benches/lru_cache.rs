@@ -0,0 +1,92 @@
+use std::collections::VecDeque;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, SamplingMode};
+use rust_project_fiit_stu::lru_cache::LruCache;
+
+criterion_group!(lru_cache, bench_lru);
+criterion_main!(lru_cache);
+
+// number of lookups, every miss is followed by a put
+const N: usize = 100000;
+const CAPACITIES: [usize; 3] = [10, 100, 1000];
+
+// the straightforward cache without the map, entries from the most to the
+// least recently used one, every lookup is a linear search
+struct VecDequeCache<K, V> {
+    entries: VecDeque<(K, V)>,
+    capacity: usize,
+}
+
+impl<K: PartialEq, V> VecDequeCache<K, V> {
+    fn new(capacity: usize) -> Self {
+        VecDequeCache {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        let i = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(i).unwrap();
+        self.entries.push_front(entry);
+        self.entries.front().map(|(_, v)| v)
+    }
+
+    fn put(&mut self, key: K, val: V) {
+        if let Some(i) = self.entries.iter().position(|(k, _)| *k == key) {
+            self.entries.remove(i);
+        } else if self.entries.len() == self.capacity {
+            self.entries.pop_back();
+        }
+        self.entries.push_front((key, val));
+    }
+}
+
+// deterministic pseudo random keys from twice the capacity, so about half of
+// the lookups hit
+fn keys(capacity: usize) -> Vec<usize> {
+    let mut x: usize = 42;
+    (0..N)
+        .map(|_| {
+            x = x
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (x >> 33) % (2 * capacity)
+        })
+        .collect()
+}
+
+fn bench_lru(c: &mut Criterion) {
+    let mut group = c.benchmark_group("LruCache");
+    group.sampling_mode(SamplingMode::Flat);
+
+    for capacity in CAPACITIES {
+        let keys = keys(capacity);
+
+        group.bench_with_input(
+            BenchmarkId::new("DLL + HashMap", capacity),
+            &keys,
+            |b, keys| {
+                b.iter(|| {
+                    let mut cache = LruCache::new(capacity);
+                    for &key in keys {
+                        if black_box(cache.get(&key)).is_none() {
+                            cache.put(key, key);
+                        }
+                    }
+                })
+            },
+        );
+
+        group.bench_with_input(BenchmarkId::new("VecDeque", capacity), &keys, |b, keys| {
+            b.iter(|| {
+                let mut cache = VecDequeCache::new(capacity);
+                for &key in keys {
+                    if black_box(cache.get(&key)).is_none() {
+                        cache.put(key, key);
+                    }
+                }
+            })
+        });
+    }
+}
@@ -1,7 +1,16 @@
-use criterion::{criterion_group, criterion_main, BatchSize, Criterion, SamplingMode};
-use rust_project_fiit_stu::{dll, immutable_ll, immutable_thread_safe_ll, ll};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, SamplingMode};
+use rust_project_fiit_stu::{dll, immutable_ll, immutable_thread_safe_ll, ll, weak_dll};
 
-criterion_group!(standard_ops, bench_push, bench_pop, bench_get);
+criterion_group!(
+    standard_ops,
+    bench_push,
+    bench_pop,
+    bench_get,
+    bench_deque,
+    bench_queue,
+    bench_old_version,
+    bench_sort
+);
 criterion_main!(standard_ops);
 
 const N: usize = 100000;
@@ -12,7 +21,7 @@ fn bench_push(c: &mut Criterion) {
 
     group.bench_function("Vec", |b| {
         b.iter_batched(
-            || Vec::new(),
+            Vec::new,
             |mut v| {
                 for i in 0..N {
                     v.push(i);
@@ -24,7 +33,7 @@ fn bench_push(c: &mut Criterion) {
 
     group.bench_function("Std LL", |b| {
         b.iter_batched(
-            || std::collections::LinkedList::new(),
+            std::collections::LinkedList::new,
             |mut std_ll| {
                 for i in 0..N {
                     std_ll.push_front(i);
@@ -36,7 +45,7 @@ fn bench_push(c: &mut Criterion) {
 
     group.bench_function("LL", |b| {
         b.iter_batched(
-            || ll::LinkedList::new(),
+            ll::LinkedList::new,
             |mut ll| {
                 for i in 0..N {
                     ll.push(i);
@@ -48,7 +57,7 @@ fn bench_push(c: &mut Criterion) {
 
     group.bench_function("Immutable LL", |b| {
         b.iter_batched(
-            || immutable_ll::ImmutableLinkedList::new(),
+            immutable_ll::ImmutableLinkedList::new,
             |mut immutable_ll| {
                 for i in 0..N {
                     immutable_ll = immutable_ll.prepend(i);
@@ -60,7 +69,7 @@ fn bench_push(c: &mut Criterion) {
 
     group.bench_function("Thread Safe LL", |b| {
         b.iter_batched(
-            || immutable_thread_safe_ll::ImmutableLinkedList::new(),
+            immutable_thread_safe_ll::ImmutableLinkedList::new,
             |mut immutable_thread_safe_ll| {
                 for i in 0..N {
                     immutable_thread_safe_ll = immutable_thread_safe_ll.prepend(i);
@@ -70,9 +79,69 @@ fn bench_push(c: &mut Criterion) {
         );
     });
 
+    group.bench_function("Immutable Queue", |b| {
+        b.iter_batched(
+            immutable_ll::ImmutableQueue::new,
+            |mut immutable_queue| {
+                for i in 0..N {
+                    immutable_queue = immutable_queue.push_back(i);
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Thread Safe Queue", |b| {
+        b.iter_batched(
+            immutable_thread_safe_ll::ImmutableQueue::new,
+            |mut thread_safe_queue| {
+                for i in 0..N {
+                    thread_safe_queue = thread_safe_queue.push_back(i);
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Immutable Deque", |b| {
+        b.iter_batched(
+            immutable_ll::ImmutableDeque::new,
+            |mut immutable_deque| {
+                for i in 0..N {
+                    immutable_deque = immutable_deque.push_front(i);
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Thread Safe Deque", |b| {
+        b.iter_batched(
+            immutable_thread_safe_ll::ImmutableDeque::new,
+            |mut thread_safe_deque| {
+                for i in 0..N {
+                    thread_safe_deque = thread_safe_deque.push_front(i);
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
     group.bench_function("DLL", |b| {
         b.iter_batched(
-            || dll::DoublyLinkedList::new(),
+            dll::DoublyLinkedList::new,
+            |mut dll| {
+                for i in 0..N {
+                    dll.push_front(i);
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Weak DLL", |b| {
+        b.iter_batched(
+            weak_dll::DoublyLinkedList::new,
             |mut dll| {
                 for i in 0..N {
                     dll.push_front(i);
@@ -178,6 +247,78 @@ fn bench_pop(c: &mut Criterion) {
         );
     });
 
+    group.bench_function("Immutable Queue", |b| {
+        b.iter_batched(
+            || {
+                let mut immutable_queue = immutable_ll::ImmutableQueue::new();
+                for i in 0..N {
+                    immutable_queue = immutable_queue.push_back(i);
+                }
+                immutable_queue
+            },
+            |mut immutable_queue| {
+                for _ in 0..N {
+                    immutable_queue = immutable_queue.pop_front();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Thread Safe Queue", |b| {
+        b.iter_batched(
+            || {
+                let mut thread_safe_queue = immutable_thread_safe_ll::ImmutableQueue::new();
+                for i in 0..N {
+                    thread_safe_queue = thread_safe_queue.push_back(i);
+                }
+                thread_safe_queue
+            },
+            |mut thread_safe_queue| {
+                for _ in 0..N {
+                    thread_safe_queue = thread_safe_queue.pop_front();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Immutable Deque", |b| {
+        b.iter_batched(
+            || {
+                let mut immutable_deque = immutable_ll::ImmutableDeque::new();
+                for i in 0..N {
+                    immutable_deque = immutable_deque.push_front(i);
+                }
+                immutable_deque
+            },
+            |mut immutable_deque| {
+                for _ in 0..N {
+                    immutable_deque = immutable_deque.pop_front();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Thread Safe Deque", |b| {
+        b.iter_batched(
+            || {
+                let mut thread_safe_deque = immutable_thread_safe_ll::ImmutableDeque::new();
+                for i in 0..N {
+                    thread_safe_deque = thread_safe_deque.push_front(i);
+                }
+                thread_safe_deque
+            },
+            |mut thread_safe_deque| {
+                for _ in 0..N {
+                    thread_safe_deque = thread_safe_deque.pop_front();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
     group.bench_function("DLL", |b| {
         b.iter_batched(
             || {
@@ -195,6 +336,24 @@ fn bench_pop(c: &mut Criterion) {
             BatchSize::SmallInput,
         );
     });
+
+    group.bench_function("Weak DLL", |b| {
+        b.iter_batched(
+            || {
+                let mut dll = weak_dll::DoublyLinkedList::new();
+                for i in 0..N {
+                    dll.push_front(i);
+                }
+                dll
+            },
+            |mut dll| {
+                for _ in 0..N {
+                    dll.pop_front();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
 }
 
 fn bench_get(c: &mut Criterion) {
@@ -239,5 +398,359 @@ fn bench_get(c: &mut Criterion) {
         b.iter(|| immutable_thread_safe_ll.get(N / 2))
     });
 
-    // DoublyLinkedList does not support indexing
+    // ImmutableQueue and ImmutableDeque only give access to their ends
+
+    // the middle is the furthest from both ends
+    group.bench_function("DLL", |b| {
+        let mut dll = dll::DoublyLinkedList::new();
+        for i in 0..N {
+            dll.push_back(i);
+        }
+
+        b.iter(|| dll.get(N / 2))
+    });
+}
+
+// pushes and pops alternate between both ends of the deque
+fn bench_deque(c: &mut Criterion) {
+    let mut group = c.benchmark_group("StandardOps-Deque");
+    group.sampling_mode(SamplingMode::Flat);
+
+    group.bench_function("VecDeque", |b| {
+        b.iter_batched(
+            std::collections::VecDeque::new,
+            |mut vec_deque| {
+                for i in 0..N {
+                    vec_deque.push_front(i);
+                    vec_deque.push_back(i);
+                }
+                for _ in 0..N {
+                    vec_deque.pop_front();
+                    vec_deque.pop_back();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Std LL", |b| {
+        b.iter_batched(
+            std::collections::LinkedList::new,
+            |mut std_ll| {
+                for i in 0..N {
+                    std_ll.push_front(i);
+                    std_ll.push_back(i);
+                }
+                for _ in 0..N {
+                    std_ll.pop_front();
+                    std_ll.pop_back();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("DLL", |b| {
+        b.iter_batched(
+            dll::DoublyLinkedList::new,
+            |mut dll| {
+                for i in 0..N {
+                    dll.push_front(i);
+                    dll.push_back(i);
+                }
+                for _ in 0..N {
+                    dll.pop_front();
+                    dll.pop_back();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Weak DLL", |b| {
+        b.iter_batched(
+            weak_dll::DoublyLinkedList::new,
+            |mut dll| {
+                for i in 0..N {
+                    dll.push_front(i);
+                    dll.push_back(i);
+                }
+                for _ in 0..N {
+                    dll.pop_front();
+                    dll.pop_back();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Immutable Deque", |b| {
+        b.iter_batched(
+            immutable_ll::ImmutableDeque::new,
+            |mut immutable_deque| {
+                for i in 0..N {
+                    immutable_deque = immutable_deque.push_front(i).push_back(i);
+                }
+                for _ in 0..N {
+                    immutable_deque = immutable_deque.pop_front().pop_back();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Thread Safe Deque", |b| {
+        b.iter_batched(
+            immutable_thread_safe_ll::ImmutableDeque::new,
+            |mut thread_safe_deque| {
+                for i in 0..N {
+                    thread_safe_deque = thread_safe_deque.push_front(i).push_back(i);
+                }
+                for _ in 0..N {
+                    thread_safe_deque = thread_safe_deque.pop_front().pop_back();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+}
+
+// elements are pushed at the back and popped from the front
+fn bench_queue(c: &mut Criterion) {
+    let mut group = c.benchmark_group("StandardOps-Queue");
+    group.sampling_mode(SamplingMode::Flat);
+
+    group.bench_function("VecDeque", |b| {
+        b.iter_batched(
+            std::collections::VecDeque::new,
+            |mut vec_deque| {
+                for i in 0..N {
+                    vec_deque.push_back(i);
+                    vec_deque.push_back(i);
+                    vec_deque.pop_front();
+                }
+                for _ in 0..N {
+                    vec_deque.pop_front();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Std LL", |b| {
+        b.iter_batched(
+            std::collections::LinkedList::new,
+            |mut std_ll| {
+                for i in 0..N {
+                    std_ll.push_back(i);
+                    std_ll.push_back(i);
+                    std_ll.pop_front();
+                }
+                for _ in 0..N {
+                    std_ll.pop_front();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("LL", |b| {
+        b.iter_batched(
+            ll::LinkedList::new,
+            |mut ll| {
+                for i in 0..N {
+                    ll.push_back(i);
+                    ll.push_back(i);
+                    ll.pop();
+                }
+                for _ in 0..N {
+                    ll.pop();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("DLL", |b| {
+        b.iter_batched(
+            dll::DoublyLinkedList::new,
+            |mut dll| {
+                for i in 0..N {
+                    dll.push_back(i);
+                    dll.push_back(i);
+                    dll.pop_front();
+                }
+                for _ in 0..N {
+                    dll.pop_front();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("Weak DLL", |b| {
+        b.iter_batched(
+            weak_dll::DoublyLinkedList::new,
+            |mut dll| {
+                for i in 0..N {
+                    dll.push_back(i);
+                    dll.push_back(i);
+                    dll.pop_front();
+                }
+                for _ in 0..N {
+                    dll.pop_front();
+                }
+            },
+            BatchSize::SmallInput,
+        );
+    });
+}
+
+// Pops from an old version of the persistent queue whose front has a single
+// element and whose back has all the others, every pop of that version has to
+// reverse the back again. The same for pushes to an old version of the
+// persistent deque one push short of rebalancing. The newest version arms do
+// the same number of operations, always on the result of the previous one.
+const OLD_LEN: usize = 1000;
+const OLD_OPS: usize = 1000;
+
+fn bench_old_version(c: &mut Criterion) {
+    let mut group = c.benchmark_group("StandardOps-OldVersion");
+    group.sampling_mode(SamplingMode::Flat);
+
+    let mut queue = immutable_ll::ImmutableQueue::new();
+    for i in 0..OLD_LEN {
+        queue = queue.push_back(i);
+    }
+
+    group.bench_function("Immutable Queue old version", |b| {
+        b.iter(|| {
+            for _ in 0..OLD_OPS {
+                black_box(queue.pop_front());
+            }
+        })
+    });
+
+    group.bench_function("Immutable Queue newest version", |b| {
+        b.iter(|| {
+            let mut newest = queue.clone();
+            for _ in 0..OLD_OPS {
+                newest = newest.pop_front();
+            }
+            newest
+        })
+    });
+
+    // push_back only, the deque rebalances once the back is longer than 3 times
+    // the front + 1, the lengths are followed here to stop right before that
+    let mut deque = immutable_ll::ImmutableDeque::new();
+    let (mut front, mut back) = (0, 0);
+    while front + back < OLD_LEN || back < 3 * front + 1 {
+        deque = deque.push_back(front + back);
+        back += 1;
+        if back > 3 * front + 1 {
+            front = (front + back) / 2;
+            back = deque.len() - front;
+        }
+    }
+
+    group.bench_function("Immutable Deque old version", |b| {
+        b.iter(|| {
+            for i in 0..OLD_OPS {
+                black_box(deque.push_back(i));
+            }
+        })
+    });
+
+    group.bench_function("Immutable Deque newest version", |b| {
+        b.iter(|| {
+            let mut newest = deque.clone();
+            for i in 0..OLD_OPS {
+                newest = newest.push_back(i);
+            }
+            newest
+        })
+    });
+}
+
+// deterministic pseudo random values, so every run sorts the same data
+fn shuffled() -> Vec<usize> {
+    let mut x: usize = 42;
+    (0..N)
+        .map(|_| {
+            x = x
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            x >> 33
+        })
+        .collect()
+}
+
+fn bench_sort(c: &mut Criterion) {
+    let mut group = c.benchmark_group("StandardOps-Sort");
+    group.sampling_mode(SamplingMode::Flat);
+
+    let vals = shuffled();
+
+    group.bench_function("Vec", |b| {
+        b.iter_batched(|| vals.clone(), |mut v| v.sort(), BatchSize::SmallInput);
+    });
+
+    let ll = || {
+        let mut ll = ll::LinkedList::new();
+        for val in &vals {
+            ll.push_back(*val);
+        }
+        ll
+    };
+
+    group.bench_function("LL", |b| {
+        b.iter_batched(ll, |mut ll| ll.sort(), BatchSize::SmallInput);
+    });
+
+    // the alternative to sorting in place, collect the values, sort them and
+    // build a new list
+    group.bench_function("LL via Vec", |b| {
+        b.iter_batched(
+            ll,
+            |ll| {
+                let mut v: Vec<_> = ll.into_iter().collect();
+                v.sort();
+                let mut sorted = ll::LinkedList::new();
+                for val in v {
+                    sorted.push_back(val);
+                }
+                sorted
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    let dll = || {
+        let mut dll = dll::DoublyLinkedList::new();
+        for val in &vals {
+            dll.push_back(*val);
+        }
+        dll
+    };
+
+    group.bench_function("DLL", |b| {
+        b.iter_batched(dll, |mut dll| dll.sort(), BatchSize::SmallInput);
+    });
+
+    group.bench_function("DLL via Vec", |b| {
+        b.iter_batched(
+            dll,
+            |dll| {
+                let mut v: Vec<_> = dll.into_iter().collect();
+                v.sort();
+                let mut sorted = dll::DoublyLinkedList::new();
+                for val in v {
+                    sorted.push_back(val);
+                }
+                sorted
+            },
+            BatchSize::SmallInput,
+        );
+    });
 }
@@ -0,0 +1,152 @@
+use std::sync::atomic::Ordering::{AcqRel, Acquire};
+
+use crossbeam_epoch::{self as epoch, Atomic, Owned};
+
+use crate::immutable_thread_safe_ll::ImmutableLinkedList;
+
+// Shared handle to the current version of a thread safe persistent list.
+// Readers take a snapshot of the current version without locking, the snapshot
+// is an ordinary list sharing its nodes with the handle, so it stays valid and
+// unchanged no matter what the writers do afterwards.
+//
+// Writers follow read-copy-update, they build the new version from the current
+// one and publish it with compare and swap, if another writer published first,
+// the update is retried on top of the newer version, so no update gets lost.
+// Replaced versions are freed through epoch based reclamation, same as the
+// nodes of the Treiber stack.
+pub struct AtomicList<T> {
+    current: Atomic<ImmutableLinkedList<T>>,
+}
+
+impl<T> AtomicList<T> {
+    pub fn new(list: ImmutableLinkedList<T>) -> Self {
+        AtomicList {
+            current: Atomic::new(list),
+        }
+    }
+
+    // O(1), only the pointer to the head of the list is cloned
+    pub fn load(&self) -> ImmutableLinkedList<T> {
+        let guard = epoch::pin();
+        let current = self.current.load(Acquire, &guard);
+        // SAFETY: current is never null and can't be freed while pinned
+        unsafe { current.deref() }.clone()
+    }
+
+    pub fn store(&self, list: ImmutableLinkedList<T>) {
+        let guard = epoch::pin();
+        let old = self.current.swap(Owned::new(list), AcqRel, &guard);
+        // SAFETY: old is unlinked, other readers are done with it once they
+        // unpin
+        unsafe { guard.defer_destroy(old) };
+    }
+
+    // f may be called several times when other writers get in the way, so it
+    // should have no side effects, returns the version which was published
+    pub fn rcu<F>(&self, mut f: F) -> ImmutableLinkedList<T>
+    where
+        F: FnMut(&ImmutableLinkedList<T>) -> ImmutableLinkedList<T>,
+    {
+        let guard = epoch::pin();
+        let mut current = self.current.load(Acquire, &guard);
+
+        loop {
+            // SAFETY: current is never null and can't be freed while pinned
+            let new = Owned::new(f(unsafe { current.deref() }));
+
+            match self
+                .current
+                .compare_exchange(current, new, AcqRel, Acquire, &guard)
+            {
+                Ok(published) => {
+                    // SAFETY: the old version is unlinked, other readers are
+                    // done with it once they unpin
+                    unsafe { guard.defer_destroy(current) };
+                    return unsafe { published.deref() }.clone();
+                }
+                Err(err) => current = err.current,
+            }
+        }
+    }
+}
+
+impl<T> Default for AtomicList<T> {
+    fn default() -> Self {
+        Self::new(ImmutableLinkedList::new())
+    }
+}
+
+impl<T> Drop for AtomicList<T> {
+    fn drop(&mut self) {
+        // SAFETY: &mut self means no other thread can access the list anymore
+        unsafe {
+            let guard = epoch::unprotected();
+            drop(self.current.load(Acquire, guard).into_owned());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::thread;
+
+    use super::AtomicList;
+    use crate::immutable_thread_safe_ll::ImmutableLinkedList;
+
+    const WRITERS: usize = 4;
+    const READERS: usize = 4;
+    const N: usize = 2_000;
+
+    #[test]
+    fn basics() {
+        let list = AtomicList::default();
+        assert_eq!(list.load().head(), None);
+
+        let published = list.rcu(|old| old.prepend(1));
+        assert_eq!(published.head(), Some(&1));
+
+        let snapshot = list.load();
+        list.rcu(|old| old.prepend(2));
+        assert_eq!(list.load().iter().copied().collect::<Vec<_>>(), vec![2, 1]);
+        // snapshot is not affected by later updates
+        assert_eq!(snapshot.iter().copied().collect::<Vec<_>>(), vec![1]);
+
+        list.store(ImmutableLinkedList::new().prepend(10));
+        assert_eq!(list.load().iter().copied().collect::<Vec<_>>(), vec![10]);
+    }
+
+    #[test]
+    fn no_lost_updates() {
+        let list = AtomicList::default();
+
+        thread::scope(|s| {
+            for w in 0..WRITERS {
+                let list = &list;
+                s.spawn(move || {
+                    for i in 0..N {
+                        list.rcu(|old| old.prepend(w * N + i));
+                    }
+                });
+            }
+
+            for _ in 0..READERS {
+                s.spawn(|| {
+                    let mut last_len = 0;
+                    for _ in 0..N / 20 {
+                        let snapshot = list.load();
+                        // every snapshot is a consistent version, it only grows
+                        // and each element appears in it once
+                        let vals: HashSet<_> = snapshot.iter().collect();
+                        assert_eq!(vals.len(), snapshot.iter().count());
+                        assert!(vals.len() >= last_len);
+                        last_len = vals.len();
+                    }
+                });
+            }
+        });
+
+        let vals: HashSet<_> = list.load().iter().copied().collect();
+        assert_eq!(vals, (0..WRITERS * N).collect());
+    }
+}
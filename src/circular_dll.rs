@@ -0,0 +1,329 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::dll::{node_ref, Link, Node};
+
+// Doubly linked list closed into a ring, the next of the last node is the head
+// and the prev of the head is the last node, so the list only needs to keep the
+// head. Uses the nodes of dll.rs, every node is pointed at by two other (by
+// itself twice when it is the only one) and the head once more by the list.
+// The nodes are borrowed mutably only by the methods taking &mut self, as the
+// rule next to dll::Node requires, so front, back and the iterators can hand
+// out plain references.
+//
+// Rotating only moves the head around the ring, none of the nodes is relinked.
+pub struct CircularList<T> {
+    head: Link<T>,
+    len: usize,
+}
+
+impl<T> CircularList<T> {
+    pub fn new() -> Self {
+        CircularList { head: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        node_ref(&self.head).map(|node| &node.val)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        node_ref(&self.head)
+            .and_then(|head| node_ref(&head.prev))
+            .map(|node| &node.val)
+    }
+
+    // the new node goes between the last node and the head
+    pub fn push_back(&mut self, val: T) {
+        let node = Node::new(val);
+        match &self.head {
+            Some(head) => {
+                let last = head.borrow_mut().prev.replace(node.clone()).unwrap();
+                last.borrow_mut().next = Some(node.clone());
+                node.borrow_mut().prev = Some(last);
+                node.borrow_mut().next = Some(head.clone());
+            }
+            None => {
+                node.borrow_mut().next = Some(node.clone());
+                node.borrow_mut().prev = Some(node.clone());
+                self.head = Some(node);
+            }
+        }
+        self.len += 1;
+    }
+
+    // in a ring the front and the back are next to each other, the new node is
+    // pushed to the back and the head moved back onto it
+    pub fn push_front(&mut self, val: T) {
+        self.push_back(val);
+        self.rotate_right(1);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let old_head = self.head.take()?;
+        let next = old_head.borrow_mut().next.take().unwrap();
+        let prev = old_head.borrow_mut().prev.take().unwrap();
+        if Rc::ptr_eq(&next, &old_head) {
+            // the only node pointed at itself, once both links are gone it is
+            // only held by old_head
+            drop((next, prev));
+        } else {
+            prev.borrow_mut().next = Some(next.clone());
+            next.borrow_mut().prev = Some(prev);
+            self.head = Some(next);
+        }
+        self.len -= 1;
+        Some(Rc::try_unwrap(old_head).ok().unwrap().into_inner().val)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.rotate_right(1);
+        self.pop_front()
+    }
+
+    // Moves the head n nodes forward, the element at index n becomes the
+    // first one, same as VecDeque::rotate_left, but n can be greater than the
+    // length, the ring just goes around. The head is walked whichever way
+    // around the ring is shorter.
+    pub fn rotate_left(&mut self, n: usize) {
+        if self.len == 0 {
+            return;
+        }
+        let n = n % self.len;
+        if n <= self.len / 2 {
+            self.step_head(n, |node| node.next.clone());
+        } else {
+            self.step_head(self.len - n, |node| node.prev.clone());
+        }
+    }
+
+    // moves the head n nodes back, the last n elements become the first ones
+    pub fn rotate_right(&mut self, n: usize) {
+        if self.len == 0 {
+            return;
+        }
+        self.rotate_left(self.len - n % self.len);
+    }
+
+    fn step_head(&mut self, n: usize, step: impl Fn(&Node<T>) -> Link<T>) {
+        for _ in 0..n {
+            let head = self.head.take().unwrap();
+            self.head = step(&head.borrow());
+        }
+    }
+
+    // one lap around the ring, starting at the head
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: node_ref(&self.head),
+            remaining: self.len,
+        }
+    }
+
+    // goes around the ring forever, None only when the list is empty
+    pub fn cycle_iter(&self) -> CycleIter<'_, T> {
+        CycleIter {
+            next: node_ref(&self.head),
+        }
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let node = self.next?;
+        self.next = node_ref(&node.next);
+        Some(&node.val)
+    }
+}
+
+pub struct CycleIter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for CycleIter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next?;
+        self.next = node_ref(&node.next);
+        Some(&node.val)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a CircularList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> Default for CircularList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for CircularList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = CircularList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for CircularList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.push_back(val);
+        }
+    }
+}
+
+// [1 <-> 2 <-> 3 <-> ↺ 1], the arrow marks where the ring wraps around to the
+// head again
+impl<T: fmt::Debug> fmt::Debug for CircularList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        if let Some(head) = self.front() {
+            for val in self {
+                write!(f, "{:?} <-> ", val)?;
+            }
+            write!(f, "↺ {:?}", head)?;
+        }
+        write!(f, "]")
+    }
+}
+
+// every pop breaks the links to the popped node, the last one its links to
+// itself, so the ring is freed node by node
+impl<T> Drop for CircularList<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+// The Josephus problem, n people stand in a circle and every k-th one is
+// eliminated, going around until nobody is left. Returns the people numbered
+// from 1 in the order they are eliminated, the last one is the survivor.
+pub fn josephus(n: usize, k: usize) -> Vec<usize> {
+    assert!(k > 0, "k has to be at least 1");
+    let mut circle: CircularList<usize> = (1..=n).collect();
+    let mut order = Vec::with_capacity(n);
+    while !circle.is_empty() {
+        circle.rotate_left(k - 1);
+        order.extend(circle.pop_front());
+    }
+    order
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use super::{josephus, CircularList};
+
+    #[test]
+    fn basics() {
+        let mut list = CircularList::new();
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.front(), None);
+
+        list.push_back(2);
+        list.push_back(3);
+        list.push_front(1);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.front(), Some(&1));
+        assert_eq!(list.back(), Some(&3));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [1, 2, 3]);
+
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_front(), Some(2));
+        assert!(list.is_empty());
+
+        list.push_front(4);
+        assert_eq!(list.back(), Some(&4));
+        assert_eq!(list.pop_back(), Some(4));
+        assert_eq!(list.pop_back(), None);
+    }
+
+    #[test]
+    fn rotate() {
+        let mut list: CircularList<i32> = (0..5).collect();
+        list.rotate_left(2);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [2, 3, 4, 0, 1]);
+        // the other way around the ring
+        list.rotate_left(4);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [1, 2, 3, 4, 0]);
+        list.rotate_right(3);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [3, 4, 0, 1, 2]);
+        list.rotate_right(13);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+        list.rotate_left(5);
+        assert_eq!(list.front(), Some(&0));
+
+        let mut empty = CircularList::<i32>::new();
+        empty.rotate_left(3);
+        empty.rotate_right(3);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn cycle_iter() {
+        let list: CircularList<i32> = (1..=3).collect();
+        let vals: Vec<_> = list.cycle_iter().take(7).copied().collect();
+        assert_eq!(vals, [1, 2, 3, 1, 2, 3, 1]);
+        assert_eq!(CircularList::<i32>::new().cycle_iter().next(), None);
+    }
+
+    #[test]
+    fn debug() {
+        let mut list: CircularList<i32> = (1..=3).collect();
+        assert_eq!(format!("{:?}", list), "[1 <-> 2 <-> 3 <-> ↺ 1]");
+        list.rotate_left(1);
+        assert_eq!(format!("{:?}", list), "[2 <-> 3 <-> 1 <-> ↺ 2]");
+        assert_eq!(format!("{:?}", CircularList::from_iter([1])), "[1 <-> ↺ 1]");
+        assert_eq!(format!("{:?}", CircularList::<i32>::new()), "[]");
+    }
+
+    #[test]
+    fn josephus_order() {
+        assert_eq!(josephus(7, 3), [3, 6, 2, 7, 5, 1, 4]);
+        assert_eq!(josephus(5, 1), [1, 2, 3, 4, 5]);
+        // the survivor of the classic 41 people with every third eliminated
+        assert_eq!(josephus(41, 3).last(), Some(&31));
+        assert!(josephus(0, 2).is_empty());
+    }
+
+    // the ring is all cycles, dropping the list has to break them
+    #[test]
+    fn drop_frees_nodes() {
+        let counter = Rc::new(());
+        let list: CircularList<_> = (0..10).map(|_| counter.clone()).collect();
+        assert_eq!(Rc::strong_count(&counter), 11);
+        drop(list);
+        assert_eq!(Rc::strong_count(&counter), 1);
+
+        let long: CircularList<_> = (0..100_000).collect();
+        drop(long);
+    }
+}
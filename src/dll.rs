@@ -1,21 +1,46 @@
 use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+use std::ptr;
 use std::rc::Rc;
 
+#[cfg(any(test, feature = "refcount-probe"))]
+use crate::refcount_probe::{Counts, Recorder, Timeline};
+use crate::snapshot::{Edge, EdgeKind, NodeIds, NodeInfo, Root, Snapshot, SnapshotError};
+
 pub struct DoublyLinkedList<T> {
     head: Link<T>,
     tail: Link<T>,
+    // kept so that the index based methods know which end is closer
+    len: usize,
 }
 
-type Link<T> = Option<Rc<RefCell<Node<T>>>>;
+pub(crate) type Link<T> = Option<Handle<T>>;
+
+pub(crate) type Handle<T> = Rc<RefCell<Node<T>>>;
 
-struct Node<T> {
-    val: T,
-    next: Link<T>,
-    prev: Link<T>,
+// The nodes are shared with circular_dll.rs and lru_cache.rs, which follow the
+// same rule for borrowing them as the list itself:
+//
+// A node is only borrowed mutably (by borrow_mut, or through the raw pointers
+// of get_mut and IterMut) inside a method taking &mut self of the structure
+// holding the handles to it, and the borrow ends before the method returns. So
+// while that structure is borrowed shared nobody holds a RefMut to any of its
+// nodes, and borrow_node can skip the borrow checker of RefCell for the
+// lifetime of the shared borrow. This gives plain references instead of Ref
+// guards, which could not outlive a call to next or be returned from get and
+// Index. A Handle never leaves the crate, so nobody else can break the rule.
+pub(crate) struct Node<T> {
+    pub(crate) val: T,
+    pub(crate) next: Link<T>,
+    pub(crate) prev: Link<T>,
 }
 
 impl<T> Node<T> {
-    fn new(val: T) -> Rc<RefCell<Self>> {
+    pub(crate) fn new(val: T) -> Rc<RefCell<Self>> {
         Rc::new(RefCell::new(Node {
             val,
             next: None,
@@ -29,84 +54,587 @@ impl<T> DoublyLinkedList<T> {
         Self {
             head: None,
             tail: None,
+            len: 0,
         }
     }
 
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     // when working with doubly linked list, every node must be pointed at
     // by two other (in case of a head/tail, they are pointed at by the list)
     pub fn push_front(&mut self, val: T) {
+        self.push_front_steps(val, |_, _, _| {});
+    }
+
+    pub fn push_back(&mut self, val: T) {
+        self.push_back_steps(val, |_, _, _| {});
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.pop_front_steps(|_, _, _| {})
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.pop_back_steps(|_, _, _| {})
+    }
+
+    // The operations report every step to the probes below, the real methods
+    // pass a callback which does nothing. The comments at the end of the lines
+    // count the pointers the list and its nodes hold to each node, the labels
+    // count the strong count of the first node they name, which includes the
+    // locals, so moving a node out of a local is +1 in the comment but 0 in the
+    // label.
+    fn push_front_steps(
+        &mut self,
+        val: T,
+        mut step: impl FnMut(&Self, &'static str, &[&Handle<T>]),
+    ) {
         let new_head = Node::new(val);
+        self.len += 1;
         match self.head.take() {
             Some(old_head) => {
                 old_head.borrow_mut().prev = Some(new_head.clone()); // new_head +1 from old_head
+                step(
+                    self,
+                    "clone new_head into old_head.prev (+1)",
+                    &[&new_head, &old_head],
+                );
                 new_head.borrow_mut().next = Some(old_head); // old_head +1 from new_head
+                step(self, "move old_head into new_head.next (0)", &[&new_head]);
                 self.head = Some(new_head); // old_head -1 from list, new_head +1 from list
                                             // new_head change total = +2 | old_head change total = 0
+                step(self, "move new_head into list.head (0)", &[]);
             }
             None => {
                 self.tail = Some(new_head.clone()); // new_head +1 from list
+                step(self, "clone new_head into list.tail (+1)", &[&new_head]);
                 self.head = Some(new_head); // new_head +1 from list
                                             // new_head change total = +2
+                step(self, "move new_head into list.head (0)", &[]);
             }
         }
     }
 
-    pub fn push_back(&mut self, val: T) {
+    fn push_back_steps(
+        &mut self,
+        val: T,
+        mut step: impl FnMut(&Self, &'static str, &[&Handle<T>]),
+    ) {
         let new_tail = Node::new(val);
+        self.len += 1;
         match self.tail.take() {
             Some(old_tail) => {
                 old_tail.borrow_mut().next = Some(new_tail.clone()); // new_tail +1 from old_tail
+                step(
+                    self,
+                    "clone new_tail into old_tail.next (+1)",
+                    &[&new_tail, &old_tail],
+                );
                 new_tail.borrow_mut().prev = Some(old_tail); // old_tail +1 from new_tail
+                step(self, "move old_tail into new_tail.prev (0)", &[&new_tail]);
                 self.tail = Some(new_tail); // old_tail -1 from list.tail, new_tail +1 from list.tail
                                             // new_tail change total = +2 | old_tail change total = 0
+                step(self, "move new_tail into list.tail (0)", &[]);
             }
             None => {
-                self.head = Some(new_tail.clone()); // new_head +1 from list.head
-                self.tail = Some(new_tail); // new_head +1 from list.tail
-                                            // new_head change total = +2
+                self.head = Some(new_tail.clone()); // new_tail +1 from list.head
+                step(self, "clone new_tail into list.head (+1)", &[&new_tail]);
+                self.tail = Some(new_tail); // new_tail +1 from list.tail
+                                            // new_tail change total = +2
+                step(self, "move new_tail into list.tail (0)", &[]);
             }
         }
     }
 
-    pub fn pop_front(&mut self) -> Option<T> {
+    fn pop_front_steps(
+        &mut self,
+        mut step: impl FnMut(&Self, &'static str, &[&Handle<T>]),
+    ) -> Option<T> {
         // old_head -1 from list.head
         self.head.take().map(|old_head| {
+            self.len -= 1;
+            step(self, "take old_head out of list.head (0)", &[&old_head]);
             // new_head -1 from its previous
-            match old_head.borrow_mut().next.take() {
+            let next = old_head.borrow_mut().next.take();
+            match next {
                 Some(new_head) => {
+                    step(
+                        self,
+                        "take new_head out of old_head.next (0)",
+                        &[&old_head, &new_head],
+                    );
                     new_head.borrow_mut().prev.take(); // old_head -1 from its next
+                    step(
+                        self,
+                        "drop old_head from new_head.prev (-1)",
+                        &[&old_head, &new_head],
+                    );
                     self.head = Some(new_head); // new_head +1 from list.head
                                                 // old_head change by -2 | new_head change by 0
+                    step(self, "move new_head into list.head (0)", &[&old_head]);
                 }
                 None => {
                     self.tail.take(); // old_head -1 from list.tail
                                       // old_head change by -2
+                    step(self, "drop old_head from list.tail (-1)", &[&old_head]);
                 }
             }
             Rc::try_unwrap(old_head).ok().unwrap().into_inner().val
         })
     }
 
-    pub fn pop_back(&mut self) -> Option<T> {
+    fn pop_back_steps(
+        &mut self,
+        mut step: impl FnMut(&Self, &'static str, &[&Handle<T>]),
+    ) -> Option<T> {
         // old_tail -1 from list.tail
         self.tail.take().map(|old_tail| {
-            // new_tail -1 from its previous
-            match old_tail.borrow_mut().prev.take() {
+            self.len -= 1;
+            step(self, "take old_tail out of list.tail (0)", &[&old_tail]);
+            // new_tail -1 from its next
+            let prev = old_tail.borrow_mut().prev.take();
+            match prev {
                 Some(new_tail) => {
-                    new_tail.borrow_mut().next.take(); // old_tail -1 from its next
+                    step(
+                        self,
+                        "take new_tail out of old_tail.prev (0)",
+                        &[&old_tail, &new_tail],
+                    );
+                    new_tail.borrow_mut().next.take(); // old_tail -1 from its previous
+                    step(
+                        self,
+                        "drop old_tail from new_tail.next (-1)",
+                        &[&old_tail, &new_tail],
+                    );
                     self.tail = Some(new_tail); // new_tail +1 from list.tail
                                                 // old_tail change by -2 | new_tail change by 0
+                    step(self, "move new_tail into list.tail (0)", &[&old_tail]);
                 }
                 None => {
-                    self.tail.take(); // old_tail -1 from list.tail
+                    self.head.take(); // old_tail -1 from list.head
                                       // old_tail change by -2
+                    step(self, "drop old_tail from list.head (-1)", &[&old_tail]);
                 }
             }
             Rc::try_unwrap(old_tail).ok().unwrap().into_inner().val
         })
     }
 
-    pub fn into_iter(self) -> IntoIter<T> {
+    // Same bottom up merge sort as in ll::LinkedList. The sort relinks only the
+    // next links, so the prev links and the tail are dropped first, then every
+    // node is pointed at only by its predecessor (or the head), and they are
+    // rebuilt once the nodes are in their final order
+    pub fn sort_by(&mut self, mut cmp: impl FnMut(&T, &T) -> Ordering) {
+        let mut chain = self.unlink_prev();
+        let mut width = 1;
+        loop {
+            let mut rest = chain.take();
+            let mut merged: Link<T> = None;
+            let mut merged_last: Link<T> = None;
+            let mut runs = 0;
+            while rest.is_some() {
+                let left = rest;
+                let right = split_after(&left, width);
+                rest = split_after(&right, width);
+
+                let (run, run_last) = merge_chains(left, right, &mut cmp);
+                match merged_last.take() {
+                    Some(last) => last.borrow_mut().next = run,
+                    None => merged = run,
+                }
+                merged_last = run_last;
+                runs += 1;
+            }
+            chain = merged;
+            if runs <= 1 {
+                break;
+            }
+            width *= 2;
+        }
+        self.relink_prev(chain);
+    }
+
+    pub fn sort_by_key<K: Ord>(&mut self, mut key: impl FnMut(&T) -> K) {
+        self.sort_by(|a, b| key(a).cmp(&key(b)));
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            front: node_ref(&self.head),
+            back: node_ref(&self.tail),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            front: node_ptr(&self.head),
+            back: node_ptr(&self.tail),
+            marker: PhantomData,
+        }
+    }
+
+    // Index based access walks from whichever end is closer to the index, so
+    // at most len / 2 nodes are visited
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.handle(index).map(|node| &borrow_node(node).val)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let node = self.handle(index)?.as_ptr();
+        // SAFETY: the list is borrowed mutably, so the node is alive for the
+        // lifetime of the returned reference and nobody else is looking at it
+        Some(unsafe { &mut (*node).val })
+    }
+
+    // panics when index is greater than the length of the list
+    pub fn insert(&mut self, index: usize, val: T) {
+        assert!(index <= self.len, "index out of bounds");
+        if index == 0 {
+            return self.push_front(val);
+        }
+        if index == self.len {
+            return self.push_back(val);
+        }
+        // the new node goes between the node at index and its predecessor,
+        // both of them are somewhere in the middle, so neither is None
+        let next = self.handle(index).unwrap().clone();
+        let prev = next.borrow().prev.clone().unwrap();
+        let node = Node::new(val);
+        node.borrow_mut().prev = Some(prev.clone());
+        node.borrow_mut().next = Some(next.clone());
+        prev.borrow_mut().next = Some(node.clone());
+        next.borrow_mut().prev = Some(node);
+        self.len += 1;
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        if index == 0 {
+            return self.pop_front();
+        }
+        if index == self.len - 1 {
+            return self.pop_back();
+        }
+        let node = self.handle(index).unwrap().clone();
+        let prev = node.borrow_mut().prev.take().unwrap();
+        let next = node.borrow_mut().next.take().unwrap();
+        prev.borrow_mut().next = Some(next.clone());
+        next.borrow_mut().prev = Some(prev);
+        self.len -= 1;
+        Some(Rc::try_unwrap(node).ok().unwrap().into_inner().val)
+    }
+
+    fn handle(&self, index: usize) -> Option<&Handle<T>> {
+        if index >= self.len {
+            return None;
+        }
+        let mut link = &self.head;
+        if index < self.len / 2 {
+            for _ in 0..index {
+                link = &node_ref(link)?.next;
+            }
+        } else {
+            link = &self.tail;
+            for _ in index + 1..self.len {
+                link = &node_ref(link)?.prev;
+            }
+        }
+        link.as_ref()
+    }
+
+    // A handle stays valid for as long as its node is in the list, so whoever
+    // keeps it can move the node around without walking the list, see
+    // lru_cache.rs. The node has to be unlinked when pushed.
+    pub(crate) fn push_front_node(&mut self, node: Handle<T>) {
+        match self.head.take() {
+            Some(old_head) => {
+                old_head.borrow_mut().prev = Some(node.clone());
+                node.borrow_mut().next = Some(old_head);
+            }
+            None => self.tail = Some(node.clone()),
+        }
+        self.head = Some(node);
+        self.len += 1;
+    }
+
+    // the node has to be in this list, afterwards it is only held by the
+    // handles outside of it
+    pub(crate) fn unlink_node(&mut self, node: &Handle<T>) {
+        let prev = node.borrow_mut().prev.take();
+        let next = node.borrow_mut().next.take();
+        match &prev {
+            Some(prev) => prev.borrow_mut().next = next.clone(),
+            None => self.head = next.clone(),
+        }
+        match next {
+            Some(next) => next.borrow_mut().prev = prev,
+            None => self.tail = prev,
+        }
+        self.len -= 1;
+    }
+
+    // leaves the list empty and returns its nodes linked only by next
+    fn unlink_prev(&mut self) -> Link<T> {
+        self.tail.take();
+        self.len = 0;
+        let mut cur = self.head.clone();
+        while let Some(node) = cur {
+            node.borrow_mut().prev.take();
+            cur = node.borrow().next.clone();
+        }
+        self.head.take()
+    }
+
+    fn relink_prev(&mut self, chain: Link<T>) {
+        let mut prev: Link<T> = None;
+        let mut len = 0;
+        let mut cur = chain.clone();
+        while let Some(node) = cur {
+            node.borrow_mut().prev = prev;
+            cur = node.borrow().next.clone();
+            prev = Some(node);
+            len += 1;
+        }
+        self.head = chain;
+        self.tail = prev;
+        self.len = len;
+    }
+}
+
+impl<T: Ord> DoublyLinkedList<T> {
+    pub fn sort(&mut self) {
+        self.sort_by(T::cmp);
+    }
+
+    // merges other into this list, both lists have to be sorted, the result is
+    // sorted and other is left empty
+    pub fn merge(&mut self, other: &mut DoublyLinkedList<T>) {
+        let left = self.unlink_prev();
+        let right = other.unlink_prev();
+        let (merged, _) = merge_chains(left, right, &mut T::cmp);
+        self.relink_prev(merged);
+    }
+}
+
+// cuts the chain after n nodes and returns the rest
+fn split_after<T>(chain: &Link<T>, n: usize) -> Link<T> {
+    let mut cur = chain.clone();
+    for _ in 1..n {
+        cur = cur.and_then(|node| node.borrow().next.clone());
+    }
+    cur.and_then(|node| node.borrow_mut().next.take())
+}
+
+// links the nodes of the two sorted chains in sorted order, returns the first
+// and the last node of the merged chain
+fn merge_chains<T>(
+    mut left: Link<T>,
+    mut right: Link<T>,
+    cmp: &mut impl FnMut(&T, &T) -> Ordering,
+) -> (Link<T>, Link<T>) {
+    let mut first: Link<T> = None;
+    let mut last: Link<T> = None;
+    loop {
+        let from = match (&left, &right) {
+            (Some(l), Some(r)) if cmp(&l.borrow().val, &r.borrow().val) == Ordering::Greater => {
+                &mut right
+            }
+            (Some(_), _) => &mut left,
+            (None, Some(_)) => &mut right,
+            (None, None) => return (first, last),
+        };
+        let node = from.take().unwrap();
+        *from = node.borrow_mut().next.take();
+        match last.take() {
+            Some(last) => last.borrow_mut().next = Some(node.clone()),
+            None => first = Some(node.clone()),
+        }
+        last = Some(node);
+    }
+}
+
+// see snapshot.rs, the list has the roots head and tail
+impl<T> DoublyLinkedList<T> {
+    pub fn snapshot<V>(&self, ids: &mut NodeIds, mut val: impl FnMut(&T) -> V) -> Snapshot<V> {
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut cur = self.head.as_ref();
+        while let Some(rc) = cur {
+            let id = ids.id(Rc::as_ptr(rc));
+            let node = borrow_node(rc);
+            nodes.push(NodeInfo {
+                id,
+                val: val(&node.val),
+                refcount: Rc::strong_count(rc),
+            });
+            for (link, kind) in [(&node.next, EdgeKind::Next), (&node.prev, EdgeKind::Prev)] {
+                if let Some(to) = link {
+                    edges.push(Edge {
+                        from: id,
+                        to: ids.id(Rc::as_ptr(to)),
+                        kind,
+                    });
+                }
+            }
+            cur = node.next.as_ref();
+        }
+
+        let mut root = |name: &str, link: &Link<T>| Root {
+            name: String::from(name),
+            node: link.as_ref().map(|rc| ids.id(Rc::as_ptr(rc))),
+        };
+        let roots = vec![root("head", &self.head), root("tail", &self.tail)];
+        Snapshot {
+            nodes,
+            edges,
+            roots,
+        }
+    }
+
+    // values are written as the strings returned by fmt, e.g. |v| v.to_string()
+    pub fn to_json(&self, fmt: impl FnMut(&T) -> String) -> String {
+        self.snapshot(&mut NodeIds::new(), fmt).to_json()
+    }
+
+    // the prev edges have to mirror the next edges, refcounts are not checked,
+    // they follow from the edges
+    pub fn from_snapshot(snapshot: Snapshot<T>) -> Result<Self, SnapshotError> {
+        let links = snapshot.links()?;
+        let head = snapshot.root(&links, "head")?;
+        let chain = snapshot.chain(&links, head)?;
+        if snapshot.root(&links, "tail")? != chain.last().copied() {
+            return Err(SnapshotError::MismatchedRoot(String::from("tail")));
+        }
+        let mut prev = None;
+        for &i in &chain {
+            if links.prev[i] != prev {
+                return Err(SnapshotError::MismatchedPrev(snapshot.nodes[i].id));
+            }
+            prev = Some(i);
+        }
+
+        let mut vals = snapshot.into_vals();
+        Ok(chain.into_iter().map(|i| vals[i].take().unwrap()).collect())
+    }
+}
+
+// Refcount probes, run the operation and record the refcounts of all the nodes
+// before it, after every step of it and after it, see refcount_probe.rs
+//
+// Every step of push and pop reports what it does and the change of the strong
+// count of the first node it names, e.g. "clone new_head into old_head.prev
+// (+1)", together with the nodes which are only held by locals at that point.
+// The probes are built for the tests and with the refcount-probe feature.
+#[cfg(any(test, feature = "refcount-probe"))]
+impl<T> DoublyLinkedList<T> {
+    pub fn probe_push_front(&mut self, val: T, fmt: impl Fn(&T) -> String) -> Timeline {
+        self.probe(&fmt, |dll, step| dll.push_front_steps(val, step))
+    }
+
+    pub fn probe_push_back(&mut self, val: T, fmt: impl Fn(&T) -> String) -> Timeline {
+        self.probe(&fmt, |dll, step| dll.push_back_steps(val, step))
+    }
+
+    pub fn probe_pop_front(&mut self, fmt: impl Fn(&T) -> String) -> (Option<T>, Timeline) {
+        let mut popped = None;
+        let timeline = self.probe(&fmt, |dll, step| popped = dll.pop_front_steps(step));
+        (popped, timeline)
+    }
+
+    pub fn probe_pop_back(&mut self, fmt: impl Fn(&T) -> String) -> (Option<T>, Timeline) {
+        let mut popped = None;
+        let timeline = self.probe(&fmt, |dll, step| popped = dll.pop_back_steps(step));
+        (popped, timeline)
+    }
+
+    fn probe(
+        &mut self,
+        fmt: &impl Fn(&T) -> String,
+        op: impl FnOnce(&mut Self, &mut dyn FnMut(&Self, &'static str, &[&Handle<T>])),
+    ) -> Timeline {
+        let mut rec = Recorder::new();
+        self.record(&mut rec, fmt, "before", &[]);
+        op(self, &mut |dll, label, locals| {
+            dll.record(&mut rec, fmt, label, locals)
+        });
+        self.record(&mut rec, fmt, "after", &[]);
+        rec.finish()
+    }
+
+    // walks every node reachable from the list or from the locals along both
+    // next and prev, without cloning any of the Rcs, which would skew the counts
+    fn record(
+        &self,
+        rec: &mut Recorder,
+        fmt: &impl Fn(&T) -> String,
+        label: &'static str,
+        locals: &[&Handle<T>],
+    ) {
+        rec.step(label);
+        let mut seen = std::collections::HashSet::new();
+        // popped from the end, so the nodes are numbered from the head along
+        // next, nodes held only by locals come after them
+        let mut stack: Vec<&Handle<T>> = self.tail.iter().collect();
+        stack.extend(locals.iter().rev());
+        stack.extend(self.head.iter());
+        while let Some(rc) = stack.pop() {
+            if !seen.insert(Rc::as_ptr(rc)) {
+                continue;
+            }
+            let counts = Counts {
+                strong: Rc::strong_count(rc),
+                weak: Rc::weak_count(rc),
+            };
+            // SAFETY: the operation is paused between two of its steps, none
+            // of the nodes is borrowed mutably and the references don't
+            // outlive this call
+            match unsafe { rc.try_borrow_unguarded() } {
+                Ok(node) => {
+                    rec.node(Rc::as_ptr(rc), counts, || fmt(&node.val));
+                    stack.extend(node.prev.iter().chain(node.next.iter()));
+                }
+                Err(_) => rec.node(Rc::as_ptr(rc), counts, || String::from("?")),
+            }
+        }
+    }
+}
+
+// plain reference to the node for as long as its handle is borrowed, see the
+// rule for borrowing the nodes next to Node
+pub(crate) fn borrow_node<T>(node: &Handle<T>) -> &Node<T> {
+    // SAFETY: nobody holds a RefMut to the node while the structure holding
+    // its handle is borrowed shared, see the rule next to Node
+    unsafe { node.try_borrow_unguarded() }.unwrap()
+}
+
+pub(crate) fn node_ref<T>(link: &Link<T>) -> Option<&Node<T>> {
+    link.as_ref().map(borrow_node)
+}
+
+fn node_ptr<T>(link: &Link<T>) -> *mut Node<T> {
+    link.as_ref().map_or(ptr::null_mut(), |node| node.as_ptr())
+}
+
+impl<T> Default for DoublyLinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> IntoIterator for DoublyLinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
         IntoIter(self)
     }
 }
@@ -126,12 +654,208 @@ impl<T> DoubleEndedIterator for IntoIter<T> {
     }
 }
 
+impl<'a, T> IntoIterator for &'a DoublyLinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut DoublyLinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+// front and back meet in the middle, once they point to the same node it is
+// the last one to be returned from either end
+pub struct Iter<'a, T> {
+    front: Option<&'a Node<T>>,
+    back: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.front?;
+        if self.back.is_some_and(|back| ptr::eq(node, back)) {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.front = node_ref(&node.next);
+        }
+        Some(&node.val)
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.back?;
+        if self.front.is_some_and(|front| ptr::eq(node, front)) {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.back = node_ref(&node.prev);
+        }
+        Some(&node.val)
+    }
+}
+
+// same as Iter, but with raw pointers, every node is returned only once, so the
+// returned mutable references never alias
+pub struct IterMut<'a, T> {
+    front: *mut Node<T>,
+    back: *mut Node<T>,
+    marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front.is_null() {
+            return None;
+        }
+        let node = self.front;
+        if node == self.back {
+            self.front = ptr::null_mut();
+            self.back = ptr::null_mut();
+        } else {
+            // SAFETY: the list is borrowed mutably for 'a, so the node is alive
+            // and nobody else is looking at it
+            self.front = node_ptr(unsafe { &(*node).next });
+        }
+        // SAFETY: same as above
+        Some(unsafe { &mut (*node).val })
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.back.is_null() {
+            return None;
+        }
+        let node = self.back;
+        if node == self.front {
+            self.front = ptr::null_mut();
+            self.back = ptr::null_mut();
+        } else {
+            // SAFETY: same as in next
+            self.back = node_ptr(unsafe { &(*node).prev });
+        }
+        // SAFETY: same as in next
+        Some(unsafe { &mut (*node).val })
+    }
+}
+
+// elements are pushed to the back, so the list keeps the order of the iterator
+impl<T> FromIterator<T> for DoublyLinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut dll = DoublyLinkedList::new();
+        dll.extend(iter);
+        dll
+    }
+}
+
+impl<T> Extend<T> for DoublyLinkedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.push_back(val);
+        }
+    }
+}
+
+impl<T: Clone> Clone for DoublyLinkedList<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for DoublyLinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for DoublyLinkedList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other)
+    }
+}
+
+impl<T: Eq> Eq for DoublyLinkedList<T> {}
+
+impl<T: PartialOrd> PartialOrd for DoublyLinkedList<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord> Ord for DoublyLinkedList<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+// there is no RefCell guard to keep alive, get and get_mut give out plain
+// references (see the rule next to Node), so the list can be indexed like a Vec
+// (in O(n))
+impl<T> Index<usize> for DoublyLinkedList<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for DoublyLinkedList<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+// length first, same as ll::LinkedList
+impl<T: Hash> Hash for DoublyLinkedList<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len);
+        for val in self {
+            val.hash(state);
+        }
+    }
+}
+
 impl<T> Drop for DoublyLinkedList<T> {
     fn drop(&mut self) {
         while self.pop_front().is_some() {}
     }
 }
 
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::DoublyLinkedList;
+
+    // serialized as a plain sequence, same as ll::LinkedList, the prev links are
+    // rebuilt by pushing the values back
+    impl<T: Serialize> Serialize for DoublyLinkedList<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self)
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for DoublyLinkedList<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let vals: Vec<T> = Vec::deserialize(deserializer)?;
+            Ok(vals.into_iter().collect())
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::DoublyLinkedList;
@@ -164,4 +888,283 @@ mod test {
         assert_eq!(dll.pop_front(), Some(1));
         assert_eq!(dll.pop_front(), None);
     }
+
+    #[test]
+    fn pop_back() {
+        let mut dll = DoublyLinkedList::new();
+        assert_eq!(dll.pop_back(), None);
+
+        dll.push_back(1);
+        dll.push_back(2);
+        dll.push_front(0);
+
+        assert_eq!(dll.pop_back(), Some(2));
+        assert_eq!(dll.pop_back(), Some(1));
+        assert_eq!(dll.pop_back(), Some(0));
+        assert_eq!(dll.pop_back(), None);
+        assert_eq!(dll.pop_front(), None);
+    }
+
+    fn from_slice(vals: &[i32]) -> DoublyLinkedList<i32> {
+        let mut dll = DoublyLinkedList::new();
+        for val in vals {
+            dll.push_back(*val);
+        }
+        dll
+    }
+
+    // walks the list from both ends, so both next and prev links are checked
+    fn assert_list(dll: DoublyLinkedList<i32>, expected: &[i32]) {
+        let mut iter = dll.into_iter();
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        for _ in 0..expected.len() / 2 {
+            front.push(iter.next().unwrap());
+        }
+        while let Some(val) = iter.next_back() {
+            back.push(val);
+        }
+        back.reverse();
+        front.extend(back);
+        assert_eq!(front, expected);
+    }
+
+    #[test]
+    fn index() {
+        let mut dll = from_slice(&[0, 1, 2, 3, 4, 5]);
+        assert_eq!(dll.len(), 6);
+        for i in 0..6 {
+            assert_eq!(dll.get(i), Some(&(i as i32)));
+            assert_eq!(dll[i], i as i32);
+        }
+        assert_eq!(dll.get(6), None);
+
+        *dll.get_mut(1).unwrap() = 10;
+        dll[4] = 40;
+        assert_eq!(dll.get_mut(6), None);
+
+        dll.insert(0, -1);
+        dll.insert(7, 6);
+        // one close to the front and one close to the back
+        dll.insert(2, 100);
+        dll.insert(7, 200);
+        assert_eq!(dll.len(), 10);
+        assert_eq!(dll.remove(10), None);
+        assert_eq!(dll.remove(3), Some(10));
+        assert_eq!(dll.remove(0), Some(-1));
+        assert_eq!(dll.remove(7), Some(6));
+        assert_eq!(dll.len(), 7);
+        assert_list(dll, &[0, 100, 2, 3, 40, 200, 5]);
+
+        let mut dll = DoublyLinkedList::new();
+        assert!(dll.is_empty());
+        dll.insert(0, 1);
+        assert_eq!(dll.remove(0), Some(1));
+        assert!(dll.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn index_out_of_bounds() {
+        let dll = from_slice(&[1, 2]);
+        let _ = dll[2];
+    }
+
+    #[test]
+    fn sort() {
+        let mut dll = from_slice(&[5, 3, 8, 1, 9, 2, 7, 3, 6]);
+        dll.sort();
+        assert_eq!(dll.len(), 9);
+        assert_eq!(dll[8], 9);
+        assert_list(dll, &[1, 2, 3, 3, 5, 6, 7, 8, 9]);
+
+        let mut dll = from_slice(&[]);
+        dll.sort();
+        assert_list(dll, &[]);
+
+        let mut dll = from_slice(&[3, 1, 2]);
+        dll.sort_by(|a, b| b.cmp(a));
+        assert_list(dll, &[3, 2, 1]);
+    }
+
+    #[test]
+    fn sort_is_stable() {
+        let mut dll = DoublyLinkedList::new();
+        for (i, key) in [3, 1, 2, 1, 3, 2, 1].iter().enumerate() {
+            dll.push_back((*key, i));
+        }
+        dll.sort_by_key(|(key, _)| *key);
+        assert_eq!(
+            dll.into_iter().collect::<Vec<_>>(),
+            [(1, 1), (1, 3), (1, 6), (2, 2), (2, 5), (3, 0), (3, 4)]
+        );
+    }
+
+    #[test]
+    fn merge() {
+        let mut dll = from_slice(&[1, 4, 6]);
+        let mut other = from_slice(&[2, 3, 7, 8]);
+        dll.merge(&mut other);
+        assert_list(dll, &[1, 2, 3, 4, 6, 7, 8]);
+        assert_list(other, &[]);
+    }
+
+    #[test]
+    fn iter() {
+        let mut dll = from_slice(&[1, 2, 3, 4]);
+        assert_eq!(dll.iter().copied().collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert_eq!(dll.iter().rev().copied().collect::<Vec<_>>(), [4, 3, 2, 1]);
+
+        // both ends meet in the middle, nothing is returned twice
+        let mut iter = dll.iter();
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        let mut iter = dll.iter_mut();
+        *iter.next().unwrap() *= 10;
+        *iter.next_back().unwrap() *= 10;
+        for val in iter {
+            *val += 1;
+        }
+        assert_list(dll, &[10, 3, 4, 40]);
+
+        assert_eq!(from_slice(&[]).iter().next(), None);
+        assert_eq!(from_slice(&[]).iter_mut().next_back(), None);
+    }
+
+    #[test]
+    fn std_traits() {
+        let dll: DoublyLinkedList<_> = (1..=3).collect();
+        assert_eq!(format!("{:?}", dll), "[1, 2, 3]");
+
+        let mut other = dll.clone();
+        assert_eq!(dll, other);
+        other.extend([4, 5]);
+        assert_ne!(dll, other);
+        assert!(dll < other);
+
+        for val in &mut other {
+            *val *= 10;
+        }
+        assert_eq!((&other).into_iter().sum::<i32>(), 150);
+
+        assert_eq!(DoublyLinkedList::<i32>::default(), DoublyLinkedList::new());
+        assert_eq!(hash(&dll), hash(&(1..=3).collect()));
+        assert_ne!(hash(&dll), hash(&other));
+        assert_list(other, &[10, 20, 30, 40, 50]);
+    }
+
+    fn hash(dll: &DoublyLinkedList<i32>) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        dll.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let dll = from_slice(&[1, 2, 3]);
+        let json = serde_json::to_string(&dll).unwrap();
+        assert_eq!(json, "[1,2,3]");
+        let dll: DoublyLinkedList<i32> = serde_json::from_str(&json).unwrap();
+        assert_list(dll, &[1, 2, 3]);
+    }
+
+    // every label ends with the change of the strong count of the first node
+    // it names, ids maps the names to the node ids of the timeline, a node
+    // which was not recorded yet was just made by Node::new and is only held
+    // by its local
+    fn assert_deltas(timeline: &crate::refcount_probe::Timeline, ids: &[(&str, usize)]) {
+        let strong = |step: usize, id: usize| {
+            timeline.steps[step]
+                .counts
+                .get(id)
+                .copied()
+                .flatten()
+                .map_or(1, |counts| counts.strong as isize)
+        };
+        for (i, step) in timeline.steps.iter().enumerate() {
+            if step.label == "before" || step.label == "after" {
+                continue;
+            }
+            let name = step.label.split(' ').nth(1).unwrap();
+            let id = ids.iter().find(|(node, _)| *node == name).unwrap().1;
+            let delta = &step.label[step.label.rfind('(').unwrap() + 1..step.label.len() - 1];
+            assert_eq!(
+                strong(i, id) - strong(i - 1, id),
+                delta.parse::<isize>().unwrap(),
+                "{}",
+                step.label
+            );
+        }
+    }
+
+    #[test]
+    fn refcount_probe() {
+        use crate::refcount_probe::Counts;
+
+        let strong = |strong| Some(Counts { strong, weak: 0 });
+
+        let mut dll = from_slice(&[1, 2]);
+        let timeline = dll.probe_push_front(0, i32::to_string);
+        assert_eq!(timeline.nodes, ["#0 1", "#1 2", "#2 0"]);
+        assert_deltas(&timeline, &[("new_head", 2), ("old_head", 0)]);
+        // new_head change total = +2, the clone in the first step is the only
+        // change of the count, the other steps move the pointers around
+        assert_eq!(
+            timeline.node(2),
+            [None, strong(2), strong(2), strong(2), strong(2)]
+        );
+        // old_head change total = 0, the count stays the same in every step
+        assert!(timeline.node(0).iter().all(|counts| *counts == strong(2)));
+        assert_eq!(
+            timeline.to_string(),
+            "\
+step                                   | #0 1  | #1 2  | #2 0
+---------------------------------------+-------+-------+------
+before                                 | 2/0   | 2/0   |
+clone new_head into old_head.prev (+1) | 2/0   | 2/0   | 2/0
+move old_head into new_head.next (0)   | 2/0   | 2/0   | 2/0
+move new_head into list.head (0)       | 2/0   | 2/0   | 2/0
+after                                  | 2/0   | 2/0   | 2/0
+"
+        );
+
+        // old_head change by -2, the last step leaves it with only the local
+        let (popped, timeline) = dll.probe_pop_front(i32::to_string);
+        assert_eq!(popped, Some(0));
+        assert_deltas(&timeline, &[("old_head", 0), ("new_head", 1)]);
+        assert_eq!(
+            timeline.node(0),
+            [strong(2), strong(2), strong(2), strong(1), strong(1), None]
+        );
+        assert_eq!(
+            timeline.step("after").unwrap().counts,
+            [None, strong(2), strong(2)]
+        );
+
+        let timeline = dll.probe_push_back(3, i32::to_string);
+        assert_deltas(&timeline, &[("new_tail", 2), ("old_tail", 1)]);
+        let (_, timeline) = dll.probe_pop_back(i32::to_string);
+        assert_deltas(&timeline, &[("old_tail", 2), ("new_tail", 1)]);
+
+        let mut dll = from_slice(&[1]);
+        let (_, timeline) = dll.probe_pop_back(i32::to_string);
+        assert_deltas(&timeline, &[("old_tail", 0)]);
+        assert_eq!(timeline.node(0), [strong(2), strong(2), strong(1), None]);
+        let timeline = dll.probe_push_back(1, i32::to_string);
+        assert_deltas(&timeline, &[("new_tail", 0)]);
+        assert_eq!(timeline.node(0), [None, strong(2), strong(2), strong(2)]);
+        let (_, timeline) = dll.probe_pop_front(i32::to_string);
+        assert_deltas(&timeline, &[("old_head", 0)]);
+        let timeline = dll.probe_push_front(1, i32::to_string);
+        assert_deltas(&timeline, &[("new_head", 0)]);
+    }
 }
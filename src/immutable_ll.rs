@@ -1,86 +1,23 @@
-use std::rc::Rc;
+use crate::lazy_stream;
+use crate::persistent_deque::PersistentDeque;
+use crate::persistent_list::{self, PersistentList, RcKind};
+use crate::persistent_queue::PersistentQueue;
+use crate::persistent_zipper::PersistentZipper;
 
-pub struct ImmutableLinkedList<T> {
-    head: Link<T>,
-}
-
-type Link<T> = Option<Rc<Node<T>>>;
+// Rc based flavour of the persistent list, see persistent_list.rs
+pub type ImmutableLinkedList<T> = PersistentList<T, RcKind>;
 
-struct Node<T> {
-    val: T,
-    next: Link<T>,
-}
-
-impl<T> ImmutableLinkedList<T> {
-    pub fn new() -> Self {
-        ImmutableLinkedList { head: None }
-    }
+pub type Iter<'a, T> = persistent_list::Iter<'a, T, RcKind>;
 
-    pub fn prepend(&self, val: T) -> ImmutableLinkedList<T> {
-        ImmutableLinkedList {
-            head: Some(Rc::new(Node {
-                val,
-                next: self.head.clone(),
-            })),
-        }
-    }
+pub type IntoIter<T> = persistent_list::IntoIter<T, RcKind>;
 
-    pub fn tail(&self) -> ImmutableLinkedList<T> {
-        ImmutableLinkedList {
-            head: self.head.as_ref().and_then(|n| n.next.clone()),
-        }
-    }
+pub type ImmutableQueue<T> = PersistentQueue<T, RcKind>;
 
-    pub fn get(&self, index: usize) -> Option<&T> {
-        let mut cur = self.head.as_deref();
-        for _ in 0..index {
-            cur = cur.and_then(|n| n.next.as_deref());
-        }
-        cur.map(|n| &n.val)
-    }
+pub type ImmutableDeque<T> = PersistentDeque<T, RcKind>;
 
-    pub fn head(&self) -> Option<&T> {
-        self.head.as_ref().map(|n| &n.val)
-    }
+pub type Zipper<T> = PersistentZipper<T, RcKind>;
 
-    pub fn iter(&self) -> Iter<T> {
-        Iter {
-            next: self.head.as_deref(),
-        }
-    }
-}
-
-pub struct Iter<'a, T> {
-    next: Option<&'a Node<T>>,
-}
-
-impl<'a, T> Iterator for Iter<'a, T> {
-    type Item = &'a T;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        // since Option<&> is copy it was just coppied and not moved
-        self.next.map(|n| {
-            // here we let compiler do the deref coercion by using the turbofish
-            // operator ::<> to let rust know what type we want, this could
-            // be also solved with as_deref()
-            self.next = n.next.as_ref().map::<&Node<T>, _>(|node| &node);
-            &n.val
-        })
-    }
-}
-
-impl<T> Drop for ImmutableLinkedList<T> {
-    fn drop(&mut self) {
-        let mut head = self.head.take();
-        while let Some(node) = head {
-            if let Ok(mut node) = Rc::try_unwrap(node) {
-                head = node.next.take();
-            } else {
-                break;
-            }
-        }
-    }
-}
+pub type LazyStream<T> = lazy_stream::rc::LazyStream<T>;
 
 #[cfg(test)]
 mod test {
@@ -1,86 +1,23 @@
-use std::sync::Arc;
+use crate::lazy_stream;
+use crate::persistent_deque::PersistentDeque;
+use crate::persistent_list::{self, ArcKind, PersistentList};
+use crate::persistent_queue::PersistentQueue;
+use crate::persistent_zipper::PersistentZipper;
 
-pub struct ImmutableLinkedList<T> {
-    head: Link<T>,
-}
-
-type Link<T> = Option<Arc<Node<T>>>;
-
-struct Node<T> {
-    val: T,
-    next: Link<T>,
-}
-
-impl<T> ImmutableLinkedList<T> {
-    pub fn new() -> Self {
-        ImmutableLinkedList { head: None }
-    }
-
-    pub fn prepend(&self, val: T) -> ImmutableLinkedList<T> {
-        ImmutableLinkedList {
-            head: Some(Arc::new(Node {
-                val,
-                next: self.head.clone(),
-            })),
-        }
-    }
+// Arc based flavour of the persistent list, see persistent_list.rs
+pub type ImmutableLinkedList<T> = PersistentList<T, ArcKind>;
 
-    pub fn tail(&self) -> ImmutableLinkedList<T> {
-        ImmutableLinkedList {
-            head: self.head.as_ref().and_then(|n| n.next.clone()),
-        }
-    }
-
-    pub fn get(&self, index: usize) -> Option<&T> {
-        let mut cur = self.head.as_deref();
-        for _ in 0..index {
-            cur = cur.and_then(|n| n.next.as_deref());
-        }
-        cur.map(|n| &n.val)
-    }
+pub type Iter<'a, T> = persistent_list::Iter<'a, T, ArcKind>;
 
-    pub fn head(&self) -> Option<&T> {
-        self.head.as_ref().map(|n| &n.val)
-    }
+pub type IntoIter<T> = persistent_list::IntoIter<T, ArcKind>;
 
-    pub fn iter(&self) -> Iter<T> {
-        Iter {
-            next: self.head.as_deref(),
-        }
-    }
-}
+pub type ImmutableQueue<T> = PersistentQueue<T, ArcKind>;
 
-pub struct Iter<'a, T> {
-    next: Option<&'a Node<T>>,
-}
+pub type ImmutableDeque<T> = PersistentDeque<T, ArcKind>;
 
-impl<'a, T> Iterator for Iter<'a, T> {
-    type Item = &'a T;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        // since Option<&> is copy it was just coppied and not moved
-        self.next.map(|n| {
-            // here we let compiler do the deref coercion by using the turbofish
-            // operator ::<> to let rust know what type we want, this could
-            // be also solved with as_deref()
-            self.next = n.next.as_ref().map::<&Node<T>, _>(|node| &node);
-            &n.val
-        })
-    }
-}
+pub type Zipper<T> = PersistentZipper<T, ArcKind>;
 
-impl<T> Drop for ImmutableLinkedList<T> {
-    fn drop(&mut self) {
-        let mut head = self.head.take();
-        while let Some(node) = head {
-            if let Ok(mut node) = Arc::try_unwrap(node) {
-                head = node.next.take();
-            } else {
-                break;
-            }
-        }
-    }
-}
+pub type LazyStream<T> = lazy_stream::sync::LazyStream<T>;
 
 #[cfg(test)]
 mod test {
@@ -119,4 +56,122 @@ mod test {
         assert_eq!(iter.next(), Some(&String::from("two")));
         assert_eq!(iter.next(), Some(&String::from("one")));
     }
+
+    #[test]
+    fn share_between_threads() {
+        let ill = ImmutableLinkedList::new().prepend(1).prepend(2);
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let ill = ill.prepend(i);
+                std::thread::spawn(move || ill.iter().sum::<i32>())
+            })
+            .collect();
+
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert_eq!(handle.join().unwrap(), i as i32 + 3);
+        }
+        assert_eq!(ill.head(), Some(&2));
+    }
+}
+
+// Exhaustive interleaving tests of the Drop of the list, which unwraps the
+// nodes with Arc::try_unwrap while other threads may hold the same tails.
+// Run with:
+// RUSTFLAGS="--cfg loom" cargo test --release --lib immutable_thread_safe_ll::loom
+#[cfg(all(test, loom))]
+mod loom {
+    use loom::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+    use loom::sync::Arc;
+    use loom::thread;
+
+    use super::ImmutableLinkedList;
+
+    // counts how many times each value was dropped
+    struct Tracked {
+        id: usize,
+        drops: Arc<Vec<AtomicUsize>>,
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.drops[self.id].fetch_add(1, SeqCst);
+        }
+    }
+
+    fn tracked_list(len: usize) -> (ImmutableLinkedList<Tracked>, Arc<Vec<AtomicUsize>>) {
+        let drops = Arc::new((0..len).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>());
+        let mut list = ImmutableLinkedList::new();
+        for id in 0..len {
+            list = list.prepend(Tracked {
+                id,
+                drops: drops.clone(),
+            });
+        }
+        (list, drops)
+    }
+
+    // loom itself fails the model when an Arc is leaked, on top of that every
+    // node has to be freed exactly once
+    fn assert_freed_once(drops: &[AtomicUsize]) {
+        for count in drops {
+            assert_eq!(count.load(SeqCst), 1);
+        }
+    }
+
+    #[test]
+    fn concurrent_drop_of_clones() {
+        loom::model(|| {
+            let (list, drops) = tracked_list(3);
+            let other = list.clone();
+
+            let handle = thread::spawn(move || drop(other));
+            drop(list);
+            handle.join().unwrap();
+
+            assert_freed_once(&drops);
+        });
+    }
+
+    #[test]
+    fn concurrent_drop_of_list_and_its_tail() {
+        loom::model(|| {
+            let (list, drops) = tracked_list(3);
+            let tail = list.tail();
+
+            let handle = thread::spawn(move || {
+                let tail_of_tail = tail.tail();
+                drop(tail);
+                drop(tail_of_tail);
+            });
+            drop(list);
+            handle.join().unwrap();
+
+            assert_freed_once(&drops);
+        });
+    }
+
+    #[test]
+    fn concurrent_prepend_and_drop_of_shared_suffix() {
+        loom::model(|| {
+            let (base, drops) = tracked_list(2);
+            let shared = base.clone();
+            let extra = Arc::new(vec![AtomicUsize::new(0)]);
+            let extra_drops = extra.clone();
+
+            let handle = thread::spawn(move || {
+                let longer = shared.prepend(Tracked {
+                    id: 0,
+                    drops: extra_drops,
+                });
+                drop(shared);
+                drop(longer);
+            });
+            drop(base);
+            handle.join().unwrap();
+
+            assert_freed_once(&drops);
+            assert_freed_once(&extra);
+        });
+    }
 }
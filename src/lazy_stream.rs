@@ -0,0 +1,377 @@
+// Lazy counterpart of the persistent list, the tail of every node is a thunk
+// which is evaluated the first time somebody looks at it and the result is
+// memoized in the node. Versions of the stream share the nodes, so every element
+// is computed at most once, no matter how many versions look at it.
+//
+// Unlike the persistent list, the stream can't be generic over the pointer kind,
+// because the closures stored in the thread safe flavour have to be Send + Sync,
+// so both flavours are generated from the same macro instead.
+macro_rules! lazy_stream {
+    ($ptr:ident, $cell:ident, $thunk_lock:ident, $take_thunk:ident, $($bounds:tt)*) => {
+        pub struct LazyStream<T> {
+            node: $ptr<Node<T>>,
+        }
+
+        // None means the end of the stream
+        type Step<T> = Option<(T, LazyStream<T>)>;
+
+        type Thunk<T> = Box<dyn FnOnce() -> Step<T> $($bounds)*>;
+
+        struct Node<T> {
+            step: $cell<Step<T>>,
+            // taken out and called when the step is evaluated
+            thunk: $thunk_lock<Option<Thunk<T>>>,
+        }
+
+        impl<T: 'static $($bounds)*> LazyStream<T> {
+            pub fn new(thunk: impl FnOnce() -> Step<T> + 'static $($bounds)*) -> Self {
+                LazyStream {
+                    node: $ptr::new(Node {
+                        step: $cell::new(),
+                        thunk: $thunk_lock::new(Some(Box::new(thunk))),
+                    }),
+                }
+            }
+
+            pub fn empty() -> Self {
+                Self::evaluated(None)
+            }
+
+            pub fn cons(val: T, tail: LazyStream<T>) -> Self {
+                Self::evaluated(Some((val, tail)))
+            }
+
+            fn evaluated(step: Step<T>) -> Self {
+                LazyStream {
+                    node: $ptr::new(Node {
+                        step: $cell::from(step),
+                        thunk: $thunk_lock::new(None),
+                    }),
+                }
+            }
+
+            // infinite stream of seed, f(seed), f(f(seed)), ...
+            pub fn iterate(seed: T, f: impl Fn(&T) -> T + 'static $($bounds)*) -> Self
+            where
+                T: Clone,
+            {
+                fn go<T: Clone + 'static $($bounds)*>(
+                    val: T,
+                    f: $ptr<dyn Fn(&T) -> T $($bounds)*>,
+                ) -> LazyStream<T> {
+                    LazyStream::new(move || {
+                        let next = f(&val);
+                        Some((val, go(next, f)))
+                    })
+                }
+                go(seed, $ptr::new(f))
+            }
+
+            pub fn repeat(val: T) -> Self
+            where
+                T: Clone,
+            {
+                Self::iterate(val, T::clone)
+            }
+
+            // f produces the next element and the next state, or None to end
+            // the stream
+            pub fn unfold<S: 'static $($bounds)*>(
+                state: S,
+                f: impl Fn(S) -> Option<(T, S)> + 'static $($bounds)*,
+            ) -> Self {
+                fn go<T: 'static $($bounds)*, S: 'static $($bounds)*>(
+                    state: S,
+                    f: $ptr<dyn Fn(S) -> Option<(T, S)> $($bounds)*>,
+                ) -> LazyStream<T> {
+                    LazyStream::new(move || {
+                        let (val, state) = f(state)?;
+                        Some((val, go(state, f)))
+                    })
+                }
+                go(state, $ptr::new(f))
+            }
+
+            // evaluates the node if it wasn't evaluated yet
+            fn step(&self) -> Option<(&T, &LazyStream<T>)> {
+                self.node
+                    .step
+                    .get_or_init(|| {
+                        let thunk = $take_thunk(&self.node.thunk)
+                            .expect("unevaluated node always has a thunk");
+                        thunk()
+                    })
+                    .as_ref()
+                    .map(|(val, tail)| (val, tail))
+            }
+
+            pub fn head(&self) -> Option<&T> {
+                self.step().map(|(val, _)| val)
+            }
+
+            pub fn tail(&self) -> LazyStream<T> {
+                self.step()
+                    .map(|(_, tail)| tail.clone())
+                    .unwrap_or_else(Self::empty)
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.step().is_none()
+            }
+
+            pub fn get(&self, index: usize) -> Option<&T> {
+                self.iter().nth(index)
+            }
+
+            pub fn iter(&self) -> Iter<'_, T> {
+                Iter { next: Some(self) }
+            }
+
+            pub fn map<U: 'static $($bounds)*>(
+                &self,
+                f: impl Fn(&T) -> U + 'static $($bounds)*,
+            ) -> LazyStream<U> {
+                fn go<T: 'static $($bounds)*, U: 'static $($bounds)*>(
+                    stream: LazyStream<T>,
+                    f: $ptr<dyn Fn(&T) -> U $($bounds)*>,
+                ) -> LazyStream<U> {
+                    LazyStream::new(move || {
+                        let (val, tail) = stream.step()?;
+                        Some((f(val), go(tail.clone(), f)))
+                    })
+                }
+                go(self.clone(), $ptr::new(f))
+            }
+
+            pub fn filter(&self, pred: impl Fn(&T) -> bool + 'static $($bounds)*) -> Self
+            where
+                T: Clone,
+            {
+                fn go<T: Clone + 'static $($bounds)*>(
+                    stream: LazyStream<T>,
+                    pred: $ptr<dyn Fn(&T) -> bool $($bounds)*>,
+                ) -> LazyStream<T> {
+                    LazyStream::new(move || {
+                        // skipping in a loop, so a long run of rejected elements
+                        // doesn't nest the thunks
+                        let mut cur = stream;
+                        loop {
+                            let next = match cur.step() {
+                                Some((val, tail)) if pred(val) => {
+                                    return Some((val.clone(), go(tail.clone(), pred)));
+                                }
+                                Some((_, tail)) => tail.clone(),
+                                None => return None,
+                            };
+                            cur = next;
+                        }
+                    })
+                }
+                go(self.clone(), $ptr::new(pred))
+            }
+
+            pub fn take(&self, n: usize) -> Self
+            where
+                T: Clone,
+            {
+                if n == 0 {
+                    return Self::empty();
+                }
+                let stream = self.clone();
+                LazyStream::new(move || {
+                    let (val, tail) = stream.step()?;
+                    Some((val.clone(), tail.take(n - 1)))
+                })
+            }
+
+            pub fn zip<U: Clone + 'static $($bounds)*>(
+                &self,
+                other: &LazyStream<U>,
+            ) -> LazyStream<(T, U)>
+            where
+                T: Clone,
+            {
+                let (left, right) = (self.clone(), other.clone());
+                LazyStream::new(move || {
+                    let (l, l_tail) = left.step()?;
+                    let (r, r_tail) = right.step()?;
+                    Some(((l.clone(), r.clone()), l_tail.zip(r_tail)))
+                })
+            }
+        }
+
+        // cloning a stream is O(1), the nodes and their memoized values are
+        // shared between both streams
+        impl<T> Clone for LazyStream<T> {
+            fn clone(&self) -> Self {
+                LazyStream {
+                    node: self.node.clone(),
+                }
+            }
+        }
+
+        pub struct Iter<'a, T> {
+            next: Option<&'a LazyStream<T>>,
+        }
+
+        impl<'a, T: 'static $($bounds)*> Iterator for Iter<'a, T> {
+            type Item = &'a T;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                let (val, tail) = self.next?.step()?;
+                self.next = Some(tail);
+                Some(val)
+            }
+        }
+
+        // same as with the persistent list, the evaluated nodes are unlinked
+        // one by one while we are their only owner, so dropping a long stream
+        // doesn't overflow the stack
+        impl<T> Drop for LazyStream<T> {
+            fn drop(&mut self) {
+                let mut cur = $ptr::get_mut(&mut self.node).and_then(|n| n.step.take());
+                while let Some(Some((_, mut tail))) = cur {
+                    cur = $ptr::get_mut(&mut tail.node).and_then(|n| n.step.take());
+                }
+            }
+        }
+    };
+}
+
+pub mod rc {
+    use std::cell::{OnceCell, RefCell};
+    use std::rc::Rc;
+
+    fn take_thunk<F>(thunk: &RefCell<Option<F>>) -> Option<F> {
+        thunk.borrow_mut().take()
+    }
+
+    lazy_stream!(Rc, OnceCell, RefCell, take_thunk,);
+
+    #[cfg(test)]
+    mod test {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        use super::LazyStream;
+
+        #[test]
+        fn infinite() {
+            let naturals = LazyStream::iterate(0, |n| n + 1);
+            assert_eq!(
+                naturals.take(5).iter().copied().collect::<Vec<_>>(),
+                vec![0, 1, 2, 3, 4]
+            );
+            assert_eq!(naturals.get(100), Some(&100));
+
+            let ones = LazyStream::repeat(1);
+            assert_eq!(ones.take(3).iter().sum::<i32>(), 3);
+        }
+
+        #[test]
+        fn unfold() {
+            let fib = LazyStream::unfold((0u64, 1u64), |(a, b)| Some((a, (b, a + b))));
+            assert_eq!(
+                fib.take(8).iter().copied().collect::<Vec<_>>(),
+                vec![0, 1, 1, 2, 3, 5, 8, 13]
+            );
+
+            let countdown = LazyStream::unfold(3, |n| if n == 0 { None } else { Some((n, n - 1)) });
+            assert_eq!(countdown.iter().copied().collect::<Vec<_>>(), vec![3, 2, 1]);
+        }
+
+        #[test]
+        fn combinators() {
+            let naturals = LazyStream::iterate(0, |n| n + 1);
+            let even_squares = naturals.filter(|n| n % 2 == 0).map(|n| n * n);
+            assert_eq!(
+                even_squares.take(4).iter().copied().collect::<Vec<_>>(),
+                vec![0, 4, 16, 36]
+            );
+
+            let letters = LazyStream::cons('a', LazyStream::cons('b', LazyStream::empty()));
+            let zipped = naturals.zip(&letters);
+            assert_eq!(
+                zipped.iter().cloned().collect::<Vec<_>>(),
+                vec![(0, 'a'), (1, 'b')]
+            );
+            assert!(zipped.tail().tail().is_empty());
+        }
+
+        #[test]
+        fn evaluated_at_most_once() {
+            let calls = Rc::new(Cell::new(0));
+            let counter = calls.clone();
+            let stream = LazyStream::iterate(0, |n| n + 1).map(move |n| {
+                counter.set(counter.get() + 1);
+                n * 10
+            });
+
+            // nothing is evaluated until somebody asks for it
+            assert_eq!(calls.get(), 0);
+
+            let version = stream.tail();
+            assert_eq!(version.get(2), Some(&30));
+            assert_eq!(calls.get(), 4);
+
+            // the other version shares the already evaluated nodes
+            assert_eq!(stream.take(4).iter().sum::<i32>(), 60);
+            assert_eq!(calls.get(), 4);
+        }
+
+        #[test]
+        fn long_stream_drop() {
+            let stream = LazyStream::iterate(0, |n| n + 1);
+            assert_eq!(stream.get(100_000), Some(&100_000));
+            // would overflow the stack with a recursive drop
+            drop(stream);
+        }
+    }
+}
+
+pub mod sync {
+    use std::sync::{Arc, Mutex, OnceLock};
+
+    fn take_thunk<F>(thunk: &Mutex<Option<F>>) -> Option<F> {
+        thunk.lock().unwrap().take()
+    }
+
+    lazy_stream!(
+        Arc,
+        OnceLock,
+        Mutex,
+        take_thunk,
+        + Send + Sync
+    );
+
+    #[cfg(test)]
+    mod test {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        use super::LazyStream;
+
+        #[test]
+        fn shared_between_threads() {
+            let calls = Arc::new(AtomicUsize::new(0));
+            let counter = calls.clone();
+            let stream = LazyStream::iterate(0u64, |n| n + 1).map(move |n| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                n * 2
+            });
+
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    let stream = stream.clone();
+                    std::thread::spawn(move || stream.take(100).iter().sum::<u64>())
+                })
+                .collect();
+
+            for handle in handles {
+                assert_eq!(handle.join().unwrap(), 9900);
+            }
+            // every element was evaluated by exactly one of the threads
+            assert_eq!(calls.load(Ordering::SeqCst), 100);
+        }
+    }
+}
@@ -1,4 +1,20 @@
+pub mod atomic_list;
+pub mod circular_dll;
 pub mod dll;
 pub mod immutable_ll;
 pub mod immutable_thread_safe_ll;
+pub mod lazy_stream;
 pub mod ll;
+pub mod lru_cache;
+pub mod ms_queue;
+pub mod par_iter;
+pub mod persistent_deque;
+pub mod persistent_list;
+pub mod persistent_queue;
+pub mod persistent_zipper;
+pub mod refcount_probe;
+pub mod snapshot;
+pub mod snapshot_diff;
+pub mod thread_safe_dll;
+pub mod treiber_stack;
+pub mod weak_dll;
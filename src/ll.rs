@@ -1,57 +1,124 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr::{self, NonNull};
+
+use crate::snapshot::{Edge, EdgeKind, NodeIds, NodeInfo, Root, Snapshot, SnapshotError};
+
+// The nodes are allocated with Box::into_raw and the list owns them through raw
+// pointers, every node is freed with Box::from_raw once it is unlinked. Keeping
+// the nodes in boxes and pointing the tail into the last one doesn't work, the
+// box asserts unique access to its node whenever it is moved or borrowed, which
+// invalidates the tail pointer under Stacked Borrows, even though the node
+// itself never moves. With raw ownership the head, the next links and the tail
+// are all equal pointers into the same allocation.
 pub struct LinkedList<T> {
     head: Link<T>,
+    // the last node, None when the list is empty
+    tail: Link<T>,
+    // the list owns the nodes and drops their values
+    marker: PhantomData<Box<Node<T>>>,
 }
 
-// No overhead for putting Box into Option, because of the null pointer optimization
-type Link<T> = Option<Box<Node<T>>>;
+// No overhead for putting NonNull into Option, because of the null pointer
+// optimization
+type Link<T> = Option<NonNull<Node<T>>>;
 
 struct Node<T> {
     val: T,
     next: Link<T>,
 }
 
+// the node is owned by the list from here on, it has to be freed with
+// Box::from_raw
+fn new_node<T>(node: Box<Node<T>>) -> NonNull<Node<T>> {
+    // SAFETY: Box::into_raw never returns null
+    unsafe { NonNull::new_unchecked(Box::into_raw(node)) }
+}
+
 impl<T> LinkedList<T> {
     pub fn new() -> Self {
-        LinkedList { head: None }
+        LinkedList {
+            head: None,
+            tail: None,
+            marker: PhantomData,
+        }
     }
 
     pub fn push(&mut self, val: T) {
-        let new = Box::new(Node {
+        let new = new_node(Box::new(Node {
             val,
-            next: self.head.take(),
-        });
+            next: self.head,
+        }));
+        if self.tail.is_none() {
+            self.tail = Some(new);
+        }
         self.head = Some(new);
     }
 
+    // O(1) thanks to the tail pointer, together with pop this makes the list
+    // a FIFO queue
+    pub fn push_back(&mut self, val: T) {
+        let new = new_node(Box::new(Node { val, next: None }));
+        match self.tail {
+            // SAFETY: the tail points to the last node of the list
+            Some(tail) => unsafe { (*tail.as_ptr()).next = Some(new) },
+            None => self.head = Some(new),
+        }
+        self.tail = Some(new);
+    }
+
     pub fn pop(&mut self) -> Option<T> {
-        self.head.take().map(|n| {
-            self.head = n.next;
-            n.val
+        self.head.map(|head| {
+            // SAFETY: the head was allocated by new_node and is unlinked
+            // right away, nothing else frees it
+            let node = unsafe { Box::from_raw(head.as_ptr()) };
+            self.head = node.next;
+            if self.head.is_none() {
+                self.tail = None;
+            }
+            node.val
         })
     }
 
-    pub fn get(&self, index: usize) -> Option<&T> {
-        let mut cur = self.head.as_deref();
-        for _ in 0..index {
-            cur = cur.and_then(|n| n.next.as_deref());
+    // moves all nodes of other to the end of this list in O(1), other is left
+    // empty
+    pub fn append(&mut self, other: &mut LinkedList<T>) {
+        if other.tail.is_none() {
+            return;
+        }
+        match self.tail {
+            // SAFETY: the tail points to the last node of the list
+            Some(tail) => unsafe { (*tail.as_ptr()).next = other.head.take() },
+            None => self.head = other.head.take(),
         }
-        cur.map(|n| &n.val)
+        self.tail = other.tail.take();
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.iter().nth(index)
     }
 
     pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
-        let mut cur = self.head.as_deref_mut();
-        for _ in 0..index {
-            cur = cur.and_then(|n| n.next.as_deref_mut());
-        }
-        cur.map(|n| &mut n.val)
+        self.iter_mut().nth(index)
     }
 
     pub fn peek(&self) -> Option<&T> {
-        self.head.as_ref().map(|n| &n.val)
+        // SAFETY: the head lives as long as the borrow of the list
+        self.head.map(|head| unsafe { &(*head.as_ptr()).val })
     }
 
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter { ll: self }
+    pub fn peek_back(&self) -> Option<&T> {
+        // SAFETY: the tail points to the last node of the list, which lives as
+        // long as the borrow of the list
+        self.tail.map(|tail| unsafe { &(*tail.as_ptr()).val })
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: same as in peek_back, &mut self makes the borrow unique
+        self.tail.map(|tail| unsafe { &mut (*tail.as_ptr()).val })
     }
 
     // we can omit lifetime here, the lifetime elision is applied, but to not
@@ -59,13 +126,497 @@ impl<T> LinkedList<T> {
     // lifetime '_
     pub fn iter(&self) -> Iter<'_, T> {
         Iter {
-            next: self.head.as_deref(),
+            next: self.head,
+            marker: PhantomData,
         }
     }
 
     pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut {
-            next: self.head.as_deref_mut(),
+            next: self.head,
+            marker: PhantomData,
+        }
+    }
+
+    // The following operations only relink the existing nodes, none of the
+    // values is moved or reallocated. The tail pointer has to be fixed up
+    // whenever the last node changes.
+
+    pub fn reverse(&mut self) {
+        // old head becomes the new tail
+        self.tail = self.head;
+
+        let mut reversed = None;
+        let mut cur = self.head.take();
+        while let Some(node) = cur {
+            // SAFETY: every node on the list is valid
+            cur = mem::replace(unsafe { &mut (*node.as_ptr()).next }, reversed);
+            reversed = Some(node);
+        }
+        self.head = reversed;
+    }
+
+    // this list keeps the elements [0, at) and the rest is returned, panics
+    // when at is greater than the length of the list
+    pub fn split_off(&mut self, at: usize) -> LinkedList<T> {
+        let (slot, owner) = self.slot(at).expect("split index out of bounds");
+        let mut other = LinkedList::new();
+        other.head = slot.take();
+        if other.head.is_some() {
+            other.tail = self.tail;
+            self.tail = owner;
+        }
+        other
+    }
+
+    // panics when index is greater than the length of the list
+    pub fn insert(&mut self, index: usize, val: T) {
+        self.link(index, Box::new(Node { val, next: None }));
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        self.unlink(index).map(|n| n.val)
+    }
+
+    // keeps only the elements for which pred returns true
+    pub fn retain(&mut self, mut pred: impl FnMut(&T) -> bool) {
+        // the walk leaves the tail alone, if pred panics halfway the guard
+        // looks it up again from the links, which are valid after every step
+        struct FixTail<'a, T>(&'a mut LinkedList<T>);
+
+        impl<T> Drop for FixTail<'_, T> {
+            fn drop(&mut self) {
+                self.0.fix_tail();
+            }
+        }
+
+        let guard = FixTail(self);
+        let mut last = None;
+        let mut cur = &mut guard.0.head;
+        while let Some(node) = *cur {
+            // SAFETY: every node on the list is valid, the removed one is
+            // unlinked before it is freed
+            unsafe {
+                if pred(&(*node.as_ptr()).val) {
+                    last = Some(node);
+                    cur = &mut (*node.as_ptr()).next;
+                } else {
+                    *cur = Box::from_raw(node.as_ptr()).next;
+                }
+            }
+        }
+        mem::forget(guard);
+        self.tail = last;
+    }
+
+    // swaps the nodes at the two positions, panics when any of them is out of
+    // bounds
+    pub fn swap(&mut self, i: usize, j: usize) {
+        let (i, j) = (i.min(j), i.max(j));
+        let node_j = self.unlink(j).expect("swap index out of bounds");
+        if i == j {
+            self.link(j, node_j);
+            return;
+        }
+        let node_i = self.unlink(i).unwrap();
+        self.link(i, node_j);
+        self.link(j, node_i);
+    }
+
+    // Bottom up merge sort, the list is merged in runs of width 1, 2, 4, ...
+    // until a single run is left. Unlike the top down version it doesn't need
+    // any recursion, so it runs in O(n log n) time with O(1) extra space. When
+    // two elements are equal, the one from the left run goes first, which makes
+    // the sort stable
+    pub fn sort_by(&mut self, mut cmp: impl FnMut(&T, &T) -> Ordering) {
+        let mut merge = Merge::new(self);
+        let mut width = 1;
+        loop {
+            let mut runs = 0;
+            while merge.rest.is_some() {
+                merge.left = merge.rest.take();
+                merge.right = split_after(&mut merge.left, width);
+                merge.rest = split_after(&mut merge.right, width);
+                merge.merge(&mut cmp);
+                runs += 1;
+            }
+            if runs <= 1 {
+                break;
+            }
+            width *= 2;
+            merge.restart();
+        }
+    }
+
+    pub fn sort_by_key<K: Ord>(&mut self, mut key: impl FnMut(&T) -> K) {
+        self.sort_by(|a, b| key(a).cmp(&key(b)));
+    }
+
+    // after relinking all nodes the last one has to be looked up again
+    fn fix_tail(&mut self) {
+        self.tail = None;
+        let mut cur = self.head;
+        while let Some(node) = cur {
+            self.tail = Some(node);
+            // SAFETY: every node on the list is valid
+            cur = unsafe { (*node.as_ptr()).next };
+        }
+    }
+
+    // link pointing to the node at index and the node owning that link (None
+    // when the link is the head), the link at index == len is the empty link
+    // after the last node, None when index is past it
+    fn slot(&mut self, index: usize) -> Option<(&mut Link<T>, Link<T>)> {
+        let mut slot = &mut self.head;
+        let mut owner = None;
+        for _ in 0..index {
+            let node = (*slot)?;
+            owner = Some(node);
+            // SAFETY: every node on the list is valid and borrowed through
+            // &mut self
+            slot = unsafe { &mut (*node.as_ptr()).next };
+        }
+        Some((slot, owner))
+    }
+
+    // the unlinked node is owned by the returned box again
+    fn unlink(&mut self, index: usize) -> Option<Box<Node<T>>> {
+        let (slot, owner) = self.slot(index)?;
+        // SAFETY: the node was allocated by new_node and is no longer on the
+        // list once its link is replaced
+        let mut node = unsafe { Box::from_raw(slot.take()?.as_ptr()) };
+        *slot = node.next.take();
+        let was_last = slot.is_none();
+        if was_last {
+            self.tail = owner;
+        }
+        Some(node)
+    }
+
+    // the slot is looked up before the node is handed over to the list, so the
+    // box still frees the node when index is out of bounds
+    fn link(&mut self, index: usize, mut node: Box<Node<T>>) {
+        let (slot, _) = self.slot(index).expect("index out of bounds");
+        node.next = slot.take();
+        let is_last = node.next.is_none();
+        let raw = new_node(node);
+        *slot = Some(raw);
+        if is_last {
+            self.tail = Some(raw);
+        }
+    }
+}
+
+impl<T: Ord> LinkedList<T> {
+    pub fn sort(&mut self) {
+        self.sort_by(T::cmp);
+    }
+
+    // merges other into this list, both lists have to be sorted, the result is
+    // sorted and other is left empty
+    pub fn merge(&mut self, other: &mut LinkedList<T>) {
+        let mut merge = Merge::new(self);
+        merge.left = merge.rest.take();
+        merge.right = other.head.take();
+        other.tail = None;
+        merge.merge(&mut T::cmp);
+    }
+}
+
+// cuts the list after n nodes and returns the rest
+fn split_after<T>(list: &mut Link<T>, n: usize) -> Link<T> {
+    let mut slot = list;
+    for _ in 0..n {
+        match *slot {
+            // SAFETY: every node on the list is valid
+            Some(node) => slot = unsafe { &mut (*node.as_ptr()).next },
+            None => return None,
+        }
+    }
+    slot.take()
+}
+
+// A merge in progress. The merged nodes are linked from the list head up to
+// the empty slot, the others are still in left, right and rest. If cmp panics
+// the guard links these back after the merged ones, so no node is lost, and
+// looks up the tail again. The order of the elements is unspecified then, the
+// same as after a panic in slice::sort_by. Once the merge is done the three
+// lists are empty and drop only fixes the tail
+struct Merge<'a, T> {
+    list: *mut LinkedList<T>,
+    slot: *mut Link<T>,
+    left: Link<T>,
+    right: Link<T>,
+    rest: Link<T>,
+    marker: PhantomData<&'a mut LinkedList<T>>,
+}
+
+impl<'a, T> Merge<'a, T> {
+    // all nodes of the list start in rest
+    fn new(list: &'a mut LinkedList<T>) -> Self {
+        let mut merge = Merge {
+            list,
+            slot: ptr::null_mut(),
+            left: None,
+            right: None,
+            rest: None,
+            marker: PhantomData,
+        };
+        merge.restart();
+        merge
+    }
+
+    // moves the merged nodes back to rest for the next pass
+    fn restart(&mut self) {
+        // SAFETY: the list is borrowed for 'a, its links are only reached
+        // through the raw pointers while the merge exists
+        unsafe {
+            self.rest = (*self.list).head.take();
+            self.slot = ptr::addr_of_mut!((*self.list).head);
+        }
+    }
+
+    // moves the nodes of the two sorted lists left and right in sorted order
+    // to the slot
+    fn merge(&mut self, cmp: &mut impl FnMut(&T, &T) -> Ordering) {
+        loop {
+            // SAFETY: the nodes of both lists are valid and each of them is
+            // moved to the slot exactly once, a node is taken out of its list
+            // only after cmp returned
+            unsafe {
+                let from = match (self.left, self.right) {
+                    (Some(l), Some(r))
+                        if cmp(&(*l.as_ptr()).val, &(*r.as_ptr()).val) == Ordering::Greater =>
+                    {
+                        &mut self.right
+                    }
+                    (Some(_), _) => &mut self.left,
+                    (None, Some(_)) => &mut self.right,
+                    (None, None) => return,
+                };
+                let node = from.unwrap();
+                *from = (*node.as_ptr()).next.take();
+                *self.slot = Some(node);
+                self.slot = ptr::addr_of_mut!((*node.as_ptr()).next);
+            }
+        }
+    }
+}
+
+impl<T> Drop for Merge<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the slot is empty, each unmerged list is linked to it and
+        // the slot moves on to the empty link after its last node
+        unsafe {
+            for run in [self.left.take(), self.right.take(), self.rest.take()] {
+                *self.slot = run;
+                while let Some(node) = *self.slot {
+                    self.slot = ptr::addr_of_mut!((*node.as_ptr()).next);
+                }
+            }
+            (*self.list).fix_tail();
+        }
+    }
+}
+
+impl<T: PartialEq> LinkedList<T> {
+    // removes consecutive repeated elements
+    pub fn dedup(&mut self) {
+        let mut cur = self.head;
+        while let Some(node) = cur {
+            // SAFETY: every node on the list is valid, the removed ones are
+            // unlinked before they are freed
+            unsafe {
+                let node = node.as_ptr();
+                while let Some(next) = (*node).next {
+                    if (*next.as_ptr()).val != (*node).val {
+                        break;
+                    }
+                    (*node).next = Box::from_raw(next.as_ptr()).next;
+                }
+                if (*node).next.is_none() {
+                    self.tail = NonNull::new(node);
+                }
+                cur = (*node).next;
+            }
+        }
+    }
+}
+
+// the raw pointers opt the list out of Send and Sync, but they only point to
+// the nodes owned by the list, so the list is as thread safe as its values
+unsafe impl<T: Send> Send for LinkedList<T> {}
+unsafe impl<T: Sync> Sync for LinkedList<T> {}
+
+// see snapshot.rs, the list has the roots head and tail
+impl<T> LinkedList<T> {
+    pub fn snapshot<V>(&self, ids: &mut NodeIds, mut val: impl FnMut(&T) -> V) -> Snapshot<V> {
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut prev = None;
+        for node in (NodeIter {
+            next: self.head,
+            marker: PhantomData,
+        }) {
+            let id = ids.id(node);
+            nodes.push(NodeInfo {
+                id,
+                val: val(&node.val),
+                // every node has a single owner
+                refcount: 1,
+            });
+            if let Some(from) = prev {
+                edges.push(Edge {
+                    from,
+                    to: id,
+                    kind: EdgeKind::Next,
+                });
+            }
+            prev = Some(id);
+        }
+
+        let roots = vec![
+            Root {
+                name: String::from("head"),
+                node: self.head.map(|node| ids.id(node.as_ptr())),
+            },
+            Root {
+                name: String::from("tail"),
+                node: self.tail.map(|node| ids.id(node.as_ptr())),
+            },
+        ];
+        Snapshot {
+            nodes,
+            edges,
+            roots,
+        }
+    }
+
+    // values are written as the strings returned by fmt, e.g. |v| v.to_string()
+    pub fn to_json(&self, fmt: impl FnMut(&T) -> String) -> String {
+        self.snapshot(&mut NodeIds::new(), fmt).to_json()
+    }
+
+    // refcounts are not checked, they follow from the edges
+    pub fn from_snapshot(snapshot: Snapshot<T>) -> Result<Self, SnapshotError> {
+        let links = snapshot.links()?;
+        snapshot.no_prev(&links)?;
+        let head = snapshot.root(&links, "head")?;
+        let chain = snapshot.chain(&links, head)?;
+        if snapshot.root(&links, "tail")? != chain.last().copied() {
+            return Err(SnapshotError::MismatchedRoot(String::from("tail")));
+        }
+
+        let mut vals = snapshot.into_vals();
+        Ok(chain.into_iter().map(|i| vals[i].take().unwrap()).collect())
+    }
+}
+
+// walks the nodes themselves, snapshot needs their addresses
+struct NodeIter<'a, T> {
+    next: Link<T>,
+    marker: PhantomData<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for NodeIter<'a, T> {
+    type Item = &'a Node<T>;
+    fn next(&mut self) -> Option<Self::Item> {
+        // SAFETY: the nodes live as long as the borrow of the list
+        let node = unsafe { self.next?.as_ref() };
+        self.next = node.next;
+        Some(node)
+    }
+}
+
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> IntoIterator for LinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { ll: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a LinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut LinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+// elements are pushed to the back, so the list keeps the order of the iterator
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut ll = LinkedList::new();
+        ll.extend(iter);
+        ll
+    }
+}
+
+impl<T> Extend<T> for LinkedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.push_back(val);
+        }
+    }
+}
+
+impl<T: Clone> Clone for LinkedList<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for LinkedList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other)
+    }
+}
+
+impl<T: Eq> Eq for LinkedList<T> {}
+
+impl<T: PartialOrd> PartialOrd for LinkedList<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord> Ord for LinkedList<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+// the length goes first, same as for std::collections::LinkedList, so lists
+// of lists with the same flattened elements don't collide
+impl<T: Hash> Hash for LinkedList<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.iter().count());
+        for val in self {
+            val.hash(state);
         }
     }
 }
@@ -83,26 +634,28 @@ impl<T> Iterator for IntoIter<T> {
 }
 
 pub struct Iter<'a, T> {
-    next: Option<&'a Node<T>>,
+    next: Link<T>,
+    // the iterator borrows the list, the nodes live as long as that borrow
+    marker: PhantomData<&'a Node<T>>,
 }
 
 impl<'a, T> Iterator for Iter<'a, T> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // since Option<&> is copy it was just coppied and not moved
+        // since Option<NonNull> is copy it was just coppied and not moved
         self.next.map(|n| {
-            // here we let compiler do the deref coercion by using the turbofish
-            // operator ::<> to let rust know what type we want, this could
-            // be also solved with as_deref()
-            self.next = n.next.as_ref().map::<&Node<T>, _>(|node| &node);
+            // SAFETY: the node is on the list borrowed for 'a
+            let n = unsafe { &*n.as_ptr() };
+            self.next = n.next;
             &n.val
         })
     }
 }
 
 pub struct IterMut<'a, T> {
-    next: Option<&'a mut Node<T>>,
+    next: Link<T>,
+    marker: PhantomData<&'a mut Node<T>>,
 }
 
 impl<'a, T> Iterator for IterMut<'a, T> {
@@ -110,8 +663,13 @@ impl<'a, T> Iterator for IterMut<'a, T> {
 
     fn next(&mut self) -> Option<Self::Item> {
         self.next.take().map(|n| {
-            self.next = n.next.as_deref_mut();
-            &mut n.val
+            // SAFETY: the node is on the list borrowed mutably for 'a, and
+            // every value is handed out only once, the next link is read
+            // before and never touched through the returned reference
+            unsafe {
+                self.next = (*n.as_ptr()).next;
+                &mut (*n.as_ptr()).val
+            }
         })
     }
 }
@@ -119,15 +677,38 @@ impl<'a, T> Iterator for IterMut<'a, T> {
 impl<T> Drop for LinkedList<T> {
     fn drop(&mut self) {
         let mut cur = self.head.take();
-        while let Some(mut node) = cur {
-            cur = node.next.take();
+        while let Some(node) = cur {
+            // SAFETY: the nodes were allocated by new_node and are freed
+            // once each, following the links
+            cur = unsafe { Box::from_raw(node.as_ptr()) }.next;
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::LinkedList;
+
+    // serialized as a plain sequence of the values from the head to the tail
+    impl<T: Serialize> Serialize for LinkedList<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self)
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for LinkedList<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let vals: Vec<T> = Vec::deserialize(deserializer)?;
+            Ok(vals.into_iter().collect())
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use std::vec;
+    use std::panic::{self, AssertUnwindSafe};
 
     use super::LinkedList;
 
@@ -156,7 +737,7 @@ mod test {
     #[test]
     fn into_iter() {
         let mut ll = LinkedList::new();
-        let expected = vec![4, 3, 2, 1];
+        let expected = [4, 3, 2, 1];
 
         ll.push(1);
         ll.push(2);
@@ -171,7 +752,7 @@ mod test {
     #[test]
     fn iter() {
         let mut ll = LinkedList::new();
-        let expected = vec![&4, &3, &2, &1];
+        let expected = [&4, &3, &2, &1];
 
         ll.push(1);
         ll.push(2);
@@ -193,7 +774,7 @@ mod test {
         let mut one = 1;
         let mut two = 2;
         let mut three = 3;
-        let expected = vec![&mut three, &mut two, &mut one];
+        let expected = [&mut three, &mut two, &mut one];
 
         ll.push(1);
         ll.push(2);
@@ -208,4 +789,278 @@ mod test {
             assert_eq!(expected[i], val)
         }
     }
+
+    #[test]
+    fn queue() {
+        let mut ll = LinkedList::new();
+        assert_eq!(ll.peek_back(), None);
+
+        ll.push_back(1);
+        ll.push_back(2);
+        ll.push(0);
+        ll.push_back(3);
+        assert_eq!(ll.peek(), Some(&0));
+        assert_eq!(ll.peek_back(), Some(&3));
+
+        assert_eq!(ll.pop(), Some(0));
+        assert_eq!(ll.pop(), Some(1));
+        assert_eq!(ll.pop(), Some(2));
+        assert_eq!(ll.pop(), Some(3));
+        assert_eq!(ll.peek_back(), None);
+
+        // tail was reset when the list got empty
+        ll.push_back(4);
+        ll.push(5);
+        if let Some(val) = ll.peek_back_mut() {
+            *val = 40;
+        }
+        assert_eq!(ll.iter().collect::<Vec<_>>(), [&5, &40]);
+    }
+
+    #[test]
+    fn append() {
+        let mut ll = LinkedList::new();
+        let mut other = LinkedList::new();
+
+        ll.append(&mut other);
+        assert_eq!(ll.peek_back(), None);
+
+        other.push_back(1);
+        other.push_back(2);
+        ll.append(&mut other);
+        assert_eq!(other.peek(), None);
+        assert_eq!(other.peek_back(), None);
+        assert_eq!(ll.peek_back(), Some(&2));
+
+        other.push_back(3);
+        other.push_back(4);
+        ll.append(&mut other);
+        ll.push_back(5);
+        assert_eq!(ll.into_iter().collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+
+        // the emptied list still works
+        other.push_back(6);
+        assert_eq!(other.peek_back(), Some(&6));
+    }
+
+    fn from_slice(vals: &[i32]) -> LinkedList<i32> {
+        let mut ll = LinkedList::new();
+        for val in vals {
+            ll.push_back(*val);
+        }
+        ll
+    }
+
+    // pushing to the back checks that the tail pointer was kept up to date
+    fn assert_list(mut ll: LinkedList<i32>, expected: &[i32]) {
+        ll.push_back(100);
+        assert_eq!(ll.peek_back(), Some(&100));
+        let vals: Vec<_> = ll.into_iter().collect();
+        assert_eq!(vals[..vals.len() - 1], *expected);
+    }
+
+    #[test]
+    fn reverse() {
+        let mut ll = from_slice(&[1, 2, 3]);
+        ll.reverse();
+        assert_list(ll, &[3, 2, 1]);
+
+        let mut ll = from_slice(&[]);
+        ll.reverse();
+        assert_list(ll, &[]);
+    }
+
+    #[test]
+    fn split_off() {
+        let mut ll = from_slice(&[1, 2, 3, 4]);
+        let other = ll.split_off(1);
+        assert_list(ll, &[1]);
+        assert_list(other, &[2, 3, 4]);
+
+        let mut ll = from_slice(&[1, 2]);
+        let other = ll.split_off(2);
+        assert_list(ll, &[1, 2]);
+        assert_list(other, &[]);
+
+        let mut ll = from_slice(&[1, 2]);
+        let other = ll.split_off(0);
+        assert_list(ll, &[]);
+        assert_list(other, &[1, 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_off_out_of_bounds() {
+        from_slice(&[1]).split_off(2);
+    }
+
+    #[test]
+    fn insert_remove() {
+        let mut ll = from_slice(&[1, 3]);
+        ll.insert(1, 2);
+        ll.insert(0, 0);
+        ll.insert(4, 4);
+        assert_eq!(ll.iter().copied().collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+
+        assert_eq!(ll.remove(4), Some(4));
+        assert_eq!(ll.remove(0), Some(0));
+        assert_eq!(ll.remove(1), Some(2));
+        assert_eq!(ll.remove(2), None);
+        assert_list(ll, &[1, 3]);
+    }
+
+    #[test]
+    fn retain() {
+        let mut ll = from_slice(&[1, 2, 3, 4, 5, 6]);
+        ll.retain(|x| x % 2 == 1);
+        assert_list(ll, &[1, 3, 5]);
+
+        let mut ll = from_slice(&[1, 2, 3]);
+        ll.retain(|_| false);
+        assert_list(ll, &[]);
+    }
+
+    #[test]
+    fn retain_panic() {
+        let mut ll = from_slice(&[1, 2, 3]);
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            ll.retain(|&x| if x == 2 { panic!("pred") } else { false })
+        }));
+        assert!(res.is_err());
+        assert_eq!(ll.pop(), Some(2));
+        ll.push_back(4);
+        assert_list(ll, &[3, 4]);
+    }
+
+    #[test]
+    fn dedup() {
+        let mut ll = from_slice(&[1, 1, 2, 3, 3, 3, 1, 4, 4]);
+        ll.dedup();
+        assert_list(ll, &[1, 2, 3, 1, 4]);
+    }
+
+    #[test]
+    fn swap() {
+        let mut ll = from_slice(&[1, 2, 3, 4]);
+        ll.swap(3, 0);
+        assert_eq!(ll.iter().copied().collect::<Vec<_>>(), [4, 2, 3, 1]);
+        ll.swap(1, 2);
+        ll.swap(2, 2);
+        assert_list(ll, &[4, 3, 2, 1]);
+
+        let mut ll = from_slice(&[1, 2]);
+        ll.swap(0, 1);
+        assert_list(ll, &[2, 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn swap_out_of_bounds() {
+        from_slice(&[1, 2]).swap(0, 2);
+    }
+
+    #[test]
+    fn sort() {
+        let mut ll = from_slice(&[5, 3, 8, 1, 9, 2, 7, 3, 6]);
+        ll.sort();
+        assert_list(ll, &[1, 2, 3, 3, 5, 6, 7, 8, 9]);
+
+        let mut ll = from_slice(&[]);
+        ll.sort();
+        assert_list(ll, &[]);
+
+        let mut ll = from_slice(&[3, 1, 2]);
+        ll.sort_by(|a, b| b.cmp(a));
+        assert_list(ll, &[3, 2, 1]);
+    }
+
+    #[test]
+    fn sort_is_stable() {
+        let mut ll = LinkedList::new();
+        for (i, key) in [3, 1, 2, 1, 3, 2, 1].iter().enumerate() {
+            ll.push_back((*key, i));
+        }
+        ll.sort_by_key(|(key, _)| *key);
+        assert_eq!(
+            ll.into_iter().collect::<Vec<_>>(),
+            [(1, 1), (1, 3), (1, 6), (2, 2), (2, 5), (3, 0), (3, 4)]
+        );
+    }
+
+    #[test]
+    fn merge() {
+        let mut ll = from_slice(&[1, 4, 6]);
+        let mut other = from_slice(&[2, 3, 7, 8]);
+        ll.merge(&mut other);
+        assert_list(ll, &[1, 2, 3, 4, 6, 7, 8]);
+        assert_list(other, &[]);
+    }
+
+    #[test]
+    fn sort_panic() {
+        let mut ll = from_slice(&[5, 1, 4, 2, 3, 0]);
+        let mut calls = 0;
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            ll.sort_by(|a, b| {
+                calls += 1;
+                if calls == 5 {
+                    panic!("cmp");
+                }
+                a.cmp(b)
+            })
+        }));
+        assert!(res.is_err());
+        // the order is unspecified but no element is lost and the tail is
+        // still the last node
+        ll.push_back(6);
+        assert_eq!(ll.peek_back(), Some(&6));
+        let mut vals: Vec<_> = ll.into_iter().collect();
+        vals.sort();
+        assert_eq!(vals, [0, 1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn std_traits() {
+        let ll: LinkedList<_> = (1..=3).collect();
+        assert_eq!(format!("{:?}", ll), "[1, 2, 3]");
+
+        let mut other = ll.clone();
+        assert_eq!(ll, other);
+        other.extend([4, 5]);
+        assert_ne!(ll, other);
+        assert!(ll < other);
+        assert_eq!(other.peek_back(), Some(&5));
+
+        for val in &mut other {
+            *val *= 10;
+        }
+        let mut sum = 0;
+        for val in &other {
+            sum += val;
+        }
+        assert_eq!(sum, 150);
+
+        assert_eq!(LinkedList::<i32>::default(), LinkedList::new());
+        assert_eq!(hash(&ll), hash(&(1..=3).collect()));
+        assert_ne!(hash(&ll), hash(&other));
+    }
+
+    fn hash(ll: &LinkedList<i32>) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        ll.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let ll = from_slice(&[1, 2, 3]);
+        let json = serde_json::to_string(&ll).unwrap();
+        assert_eq!(json, "[1,2,3]");
+        let ll: LinkedList<i32> = serde_json::from_str(&json).unwrap();
+        assert_list(ll, &[1, 2, 3]);
+    }
 }
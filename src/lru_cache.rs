@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+use crate::dll::{self, DoublyLinkedList, Handle, Node};
+
+// Least recently used cache, the entries are kept in a doubly linked list from
+// the most to the least recently used one, and the map points every key to the
+// node of its entry. Looking up a key, moving its node to the front and
+// evicting the last node are all O(1), no walking the list.
+//
+// The map holds a second handle to every node, so a node is pointed at once
+// more than in a plain dll::DoublyLinkedList. Both handles are kept by the
+// cache, which borrows the nodes mutably only in the methods taking &mut self,
+// as the rule next to dll::Node requires.
+//
+// benches/lru_cache.rs compares the cache with a VecDeque searched linearly,
+// which is faster for a handful of entries, but its search grows with the
+// capacity, the numbers are in bench_res/bench_res.txt.
+pub struct LruCache<K, V> {
+    map: HashMap<K, Handle<(K, V)>>,
+    list: DoublyLinkedList<(K, V)>,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    // panics when capacity is 0
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity has to be at least 1");
+        LruCache {
+            map: HashMap::with_capacity(capacity),
+            list: DoublyLinkedList::new(),
+            capacity,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // the entry becomes the most recently used one
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let node = self.map.get(key)?;
+        self.list.unlink_node(node);
+        self.list.push_front_node(node.clone());
+        Some(&dll::borrow_node(node).val.1)
+    }
+
+    // same as get, but leaves the order alone
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.map.get(key).map(|node| &dll::borrow_node(node).val.1)
+    }
+
+    // Inserts the entry as the most recently used one and returns the old value
+    // when the key was already there. A new key in a full cache evicts the
+    // least recently used entry.
+    pub fn put(&mut self, key: K, val: V) -> Option<V> {
+        if let Some(node) = self.map.get(&key) {
+            let old = mem::replace(&mut node.borrow_mut().val.1, val);
+            self.list.unlink_node(node);
+            self.list.push_front_node(node.clone());
+            return Some(old);
+        }
+        if self.list.len() == self.capacity {
+            // the handle in the map has to go first, pop_back expects to own
+            // the only handles to the node
+            let (last, _) = self.list.iter().next_back().unwrap();
+            self.map.remove(last);
+            self.list.pop_back();
+        }
+        let node = Node::new((key.clone(), val));
+        self.list.push_front_node(node.clone());
+        self.map.insert(key, node);
+        None
+    }
+
+    // from the most to the least recently used entry
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(self.list.iter())
+    }
+}
+
+pub struct Iter<'a, K, V>(dll::Iter<'a, (K, V)>);
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, val)| (key, val))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, val)| (key, val))
+    }
+}
+
+// the list pops its nodes when dropped, which only works once the map let go
+// of its handles
+impl<K, V> Drop for LruCache<K, V> {
+    fn drop(&mut self) {
+        self.map.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use super::LruCache;
+
+    fn keys(cache: &LruCache<i32, &str>) -> Vec<i32> {
+        cache.iter().map(|(key, _)| *key).collect()
+    }
+
+    #[test]
+    fn get_and_put() {
+        let mut cache = LruCache::new(3);
+        assert!(cache.is_empty());
+        assert_eq!(cache.get(&1), None);
+
+        assert_eq!(cache.put(1, "one"), None);
+        assert_eq!(cache.put(2, "two"), None);
+        assert_eq!(cache.put(3, "three"), None);
+        assert_eq!(keys(&cache), [3, 2, 1]);
+
+        assert_eq!(cache.get(&1), Some(&"one"));
+        assert_eq!(keys(&cache), [1, 3, 2]);
+        // peek does not count as a use
+        assert_eq!(cache.peek(&2), Some(&"two"));
+        assert_eq!(keys(&cache), [1, 3, 2]);
+
+        assert_eq!(cache.put(3, "THREE"), Some("three"));
+        assert_eq!(keys(&cache), [3, 1, 2]);
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn eviction() {
+        let mut cache = LruCache::new(2);
+        cache.put(1, "one");
+        cache.put(2, "two");
+        cache.get(&1);
+        cache.put(3, "three");
+        assert_eq!(cache.peek(&2), None);
+        assert_eq!(keys(&cache), [3, 1]);
+
+        cache.put(4, "four");
+        assert_eq!(keys(&cache), [4, 3]);
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.len(), cache.capacity());
+        assert_eq!(
+            cache.iter().rev().collect::<Vec<_>>(),
+            [(&3, &"three"), (&4, &"four")]
+        );
+
+        let mut single = LruCache::new(1);
+        single.put(1, "one");
+        single.put(2, "two");
+        assert_eq!(keys(&single), [2]);
+    }
+
+    #[test]
+    fn values_are_freed() {
+        let counter = Rc::new(());
+        let mut cache = LruCache::new(2);
+        for i in 0..5 {
+            cache.put(i, counter.clone());
+        }
+        assert_eq!(Rc::strong_count(&counter), 3);
+        cache.put(4, counter.clone());
+        assert_eq!(Rc::strong_count(&counter), 3);
+        drop(cache);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity has to be at least 1")]
+    fn zero_capacity() {
+        LruCache::<i32, i32>::new(0);
+    }
+}
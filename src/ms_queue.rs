@@ -0,0 +1,398 @@
+use std::mem::MaybeUninit;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+use crossbeam_epoch::{self as epoch, Atomic, Owned, Shared};
+
+// Michael-Scott lock free queue. The list always starts with a sentinel node,
+// the first element of the queue is in the node after it, so pushing (at the
+// tail) and popping (at the head) never touch the same pointer and producers
+// don't contend with consumers.
+//
+// Pushing is done in two steps, the new node is linked after the last node and
+// then the tail is swung to it. Any thread that finds the tail lagging behind
+// swings it forward on its own, so a stalled producer can't block the others.
+// Popped sentinels are freed through epoch based reclamation, same as in the
+// Treiber stack.
+pub struct MsQueue<T> {
+    head: Atomic<Node<T>>,
+    tail: Atomic<Node<T>>,
+}
+
+struct Node<T> {
+    // uninitialized in the sentinel, the value is moved out when the node
+    // becomes the new sentinel
+    val: MaybeUninit<T>,
+    next: Atomic<Node<T>>,
+}
+
+impl<T> MsQueue<T> {
+    pub fn new() -> Self {
+        let queue = MsQueue {
+            head: Atomic::null(),
+            tail: Atomic::null(),
+        };
+        // SAFETY: nobody else can see the queue yet
+        let sentinel = Owned::new(Node {
+            val: MaybeUninit::uninit(),
+            next: Atomic::null(),
+        })
+        .into_shared(unsafe { epoch::unprotected() });
+        queue.head.store(sentinel, Relaxed);
+        queue.tail.store(sentinel, Relaxed);
+        queue
+    }
+
+    pub fn push_back(&self, val: T) {
+        let guard = epoch::pin();
+        let new = Owned::new(Node {
+            val: MaybeUninit::new(val),
+            next: Atomic::null(),
+        })
+        .into_shared(&guard);
+
+        loop {
+            let tail = self.tail.load(Acquire, &guard);
+            // SAFETY: tail is never null and can't be freed while pinned
+            let tail_node = unsafe { tail.deref() };
+            let next = tail_node.next.load(Acquire, &guard);
+
+            if !next.is_null() {
+                // tail is lagging behind, help the other producer and retry
+                let _ = self
+                    .tail
+                    .compare_exchange(tail, next, Release, Relaxed, &guard);
+                continue;
+            }
+
+            if tail_node
+                .next
+                .compare_exchange(Shared::null(), new, Release, Relaxed, &guard)
+                .is_ok()
+            {
+                // if this fails somebody else already swung the tail for us
+                let _ = self
+                    .tail
+                    .compare_exchange(tail, new, Release, Relaxed, &guard);
+                return;
+            }
+        }
+    }
+
+    pub fn pop_front(&self) -> Option<T> {
+        let guard = epoch::pin();
+
+        loop {
+            let head = self.head.load(Acquire, &guard);
+            // SAFETY: head is never null and can't be freed while pinned
+            let next = unsafe { head.deref() }.next.load(Acquire, &guard);
+            // SAFETY: same as above
+            let next_node = unsafe { next.as_ref() }?;
+
+            if self
+                .head
+                .compare_exchange(head, next, Release, Relaxed, &guard)
+                .is_ok()
+            {
+                // tail must never point to a freed node
+                let tail = self.tail.load(Relaxed, &guard);
+                if tail == head {
+                    let _ = self
+                        .tail
+                        .compare_exchange(tail, next, Release, Relaxed, &guard);
+                }
+                // SAFETY: the successful swap made next the new sentinel, so
+                // this thread is the only one moving its value out, the old
+                // sentinel is destroyed once no other thread can be reading it
+                unsafe {
+                    guard.defer_destroy(head);
+                    return Some(next_node.val.assume_init_read());
+                }
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let guard = epoch::pin();
+        let head = self.head.load(Acquire, &guard);
+        // SAFETY: head is never null and can't be freed while pinned
+        unsafe { head.deref() }.next.load(Acquire, &guard).is_null()
+    }
+}
+
+// A value is moved in by push_back and read out by the one pop_front whose
+// compare and swap hands the sentinel role over to its node. From then on the
+// value of the new sentinel counts as uninitialized (MaybeUninit), so neither
+// the epoch collector freeing the old sentinel nor Drop of the queue touches
+// it again, and no reference to a value is ever handed out. So, unlike a queue
+// which gave out &T, sharing it between threads only needs T: Send.
+unsafe impl<T: Send> Send for MsQueue<T> {}
+unsafe impl<T: Send> Sync for MsQueue<T> {}
+
+impl<T> Default for MsQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for MsQueue<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+        // SAFETY: &mut self means no other thread can access the queue anymore
+        unsafe {
+            let guard = epoch::unprotected();
+            drop(self.head.load(Relaxed, guard).into_owned());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{HashSet, VecDeque};
+    use std::sync::atomic::{AtomicU64, Ordering::SeqCst};
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::MsQueue;
+
+    #[test]
+    fn basics() {
+        let q = MsQueue::new();
+        assert!(q.is_empty());
+        assert_eq!(q.pop_front(), None);
+
+        q.push_back(1);
+        q.push_back(2);
+        assert!(!q.is_empty());
+        assert_eq!(q.pop_front(), Some(1));
+
+        q.push_back(3);
+        assert_eq!(q.pop_front(), Some(2));
+        assert_eq!(q.pop_front(), Some(3));
+        assert_eq!(q.pop_front(), None);
+    }
+
+    #[test]
+    fn values_are_dropped_once() {
+        let counter = Arc::new(());
+        let q = MsQueue::new();
+        for _ in 0..10 {
+            q.push_back(counter.clone());
+        }
+        q.pop_front();
+        assert_eq!(Arc::strong_count(&counter), 10);
+        drop(q);
+        assert_eq!(Arc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn mpmc_keeps_producer_order() {
+        const PRODUCERS: usize = 4;
+        const CONSUMERS: usize = 4;
+        const N: usize = 10_000;
+
+        let q = MsQueue::new();
+
+        let consumed: Vec<Vec<(usize, usize)>> = thread::scope(|s| {
+            for p in 0..PRODUCERS {
+                let q = &q;
+                s.spawn(move || {
+                    for i in 0..N {
+                        q.push_back((p, i));
+                    }
+                });
+            }
+
+            let consumers: Vec<_> = (0..CONSUMERS)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut consumed = Vec::new();
+                        for _ in 0..N {
+                            consumed.extend(q.pop_front());
+                        }
+                        consumed
+                    })
+                })
+                .collect();
+            consumers.into_iter().map(|c| c.join().unwrap()).collect()
+        });
+
+        let mut seen = HashSet::new();
+        for vals in consumed {
+            // every consumer sees elements of one producer in the order they
+            // were pushed
+            let mut last = [None; PRODUCERS];
+            for (p, i) in vals {
+                assert!(last[p] < Some(i));
+                last[p] = Some(i);
+                assert!(seen.insert((p, i)));
+            }
+        }
+        while let Some(val) = q.pop_front() {
+            assert!(seen.insert(val));
+        }
+        assert_eq!(seen.len(), PRODUCERS * N);
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    enum Op {
+        Push(u32),
+        Pop(Option<u32>),
+    }
+
+    // single operation of a recorded history, call and ret are the ticks of a
+    // global clock taken right before the call and right after it returned
+    #[derive(Clone, Copy, Debug)]
+    struct Event {
+        op: Op,
+        call: u64,
+        ret: u64,
+    }
+
+    // Wing & Gong linearizability check, looks for an order of the operations
+    // which respects their real time order (an operation which returned before
+    // another one was called has to come first) and is a valid run of a
+    // sequential queue
+    fn is_linearizable(history: &[Event]) -> bool {
+        fn search(
+            history: &[Event],
+            done: u64,
+            model: &mut VecDeque<u32>,
+            visited: &mut HashSet<(u64, VecDeque<u32>)>,
+        ) -> bool {
+            if done.count_ones() as usize == history.len() {
+                return true;
+            }
+            if !visited.insert((done, model.clone())) {
+                return false;
+            }
+
+            let pending = || (0..history.len()).filter(|i| done & (1 << i) == 0);
+            let first_ret = pending().map(|i| history[i].ret).min().unwrap();
+
+            // only operations called before any pending one returned can go next
+            for i in pending().filter(|&i| history[i].call < first_ret) {
+                let ok = match history[i].op {
+                    Op::Push(val) => {
+                        model.push_back(val);
+                        let ok = search(history, done | (1 << i), model, visited);
+                        model.pop_back();
+                        ok
+                    }
+                    Op::Pop(None) => {
+                        model.is_empty() && search(history, done | (1 << i), model, visited)
+                    }
+                    Op::Pop(Some(val)) => {
+                        if model.front() != Some(&val) {
+                            continue;
+                        }
+                        model.pop_front();
+                        let ok = search(history, done | (1 << i), model, visited);
+                        model.push_front(val);
+                        ok
+                    }
+                };
+                if ok {
+                    return true;
+                }
+            }
+            false
+        }
+
+        search(history, 0, &mut VecDeque::new(), &mut HashSet::new())
+    }
+
+    #[test]
+    fn checker_rejects_invalid_history() {
+        // pop of 2 returned before 1 was popped, even though 1 was pushed first
+        let history = [
+            Event {
+                op: Op::Push(1),
+                call: 0,
+                ret: 1,
+            },
+            Event {
+                op: Op::Push(2),
+                call: 2,
+                ret: 3,
+            },
+            Event {
+                op: Op::Pop(Some(2)),
+                call: 4,
+                ret: 5,
+            },
+            Event {
+                op: Op::Pop(Some(1)),
+                call: 6,
+                ret: 7,
+            },
+        ];
+        assert!(!is_linearizable(&history));
+
+        // overlapping pushes can be linearized in either order
+        let history = [
+            Event {
+                op: Op::Push(1),
+                call: 0,
+                ret: 3,
+            },
+            Event {
+                op: Op::Push(2),
+                call: 1,
+                ret: 2,
+            },
+            Event {
+                op: Op::Pop(Some(2)),
+                call: 4,
+                ret: 5,
+            },
+            Event {
+                op: Op::Pop(Some(1)),
+                call: 6,
+                ret: 7,
+            },
+        ];
+        assert!(is_linearizable(&history));
+    }
+
+    #[test]
+    fn linearizable_histories() {
+        const THREADS: u32 = 3;
+        const OPS: u32 = 6;
+
+        for round in 0..200 {
+            let q = MsQueue::new();
+            let clock = AtomicU64::new(0);
+
+            let history: Vec<Event> = thread::scope(|s| {
+                let threads: Vec<_> = (0..THREADS)
+                    .map(|t| {
+                        let (q, clock) = (&q, &clock);
+                        s.spawn(move || {
+                            let mut events = Vec::new();
+                            for i in 0..OPS {
+                                let call = clock.fetch_add(1, SeqCst);
+                                // mix of pushes and pops, different in every round
+                                let op = if (round + t + i) % 3 == 0 {
+                                    Op::Pop(q.pop_front())
+                                } else {
+                                    let val = t * OPS + i;
+                                    q.push_back(val);
+                                    Op::Push(val)
+                                };
+                                let ret = clock.fetch_add(1, SeqCst);
+                                events.push(Event { op, call, ret });
+                            }
+                            events
+                        })
+                    })
+                    .collect();
+                threads
+                    .into_iter()
+                    .flat_map(|t| t.join().unwrap())
+                    .collect()
+            });
+
+            assert!(is_linearizable(&history), "{:?}", history);
+        }
+    }
+}
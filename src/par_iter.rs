@@ -0,0 +1,59 @@
+use crate::persistent_list::{ArcKind, PersistentList};
+
+// A linked list can't be split in the middle without walking to it, so the
+// references to the values are collected into a Vec first (one sequential
+// O(n) pass) and rayon splits the Vec between the threads. This pays off when
+// the work done for every element outweighs the walk over the nodes.
+#[cfg(feature = "rayon")]
+pub type ParIter<'a, T> = rayon::vec::IntoIter<&'a T>;
+
+// without the rayon feature par_iter falls back to the sequential iterator,
+// code using only the adapters shared by both (map, filter, sum, count, ...)
+// works with either of them
+#[cfg(not(feature = "rayon"))]
+pub type ParIter<'a, T> = crate::persistent_list::Iter<'a, T, ArcKind>;
+
+impl<T: Send + Sync> PersistentList<T, ArcKind> {
+    #[cfg(feature = "rayon")]
+    pub fn par_iter(&self) -> ParIter<'_, T> {
+        use rayon::iter::IntoParallelIterator;
+
+        self.iter().collect::<Vec<_>>().into_par_iter()
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    pub fn par_iter(&self) -> ParIter<'_, T> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "rayon")]
+    use rayon::iter::ParallelIterator;
+
+    use crate::immutable_thread_safe_ll::ImmutableLinkedList;
+
+    #[test]
+    fn same_as_sequential() {
+        let mut list = ImmutableLinkedList::new();
+        for i in 0..10_000u64 {
+            list = list.prepend(i);
+        }
+
+        assert_eq!(
+            list.par_iter().map(|x| x * 2).sum::<u64>(),
+            list.iter().map(|x| x * 2).sum::<u64>()
+        );
+        assert_eq!(
+            list.par_iter().filter(|x| *x % 3 == 0).count(),
+            list.iter().filter(|x| *x % 3 == 0).count()
+        );
+        // order of the elements is kept
+        assert_eq!(
+            list.par_iter().copied().collect::<Vec<_>>(),
+            list.iter().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(ImmutableLinkedList::<u64>::new().par_iter().count(), 0);
+    }
+}
@@ -0,0 +1,278 @@
+use crate::persistent_list::{self, PersistentList, SharedPointerKind};
+
+// neither list can grow over C times the other one (+1), otherwise a pop on the
+// shorter side could run out of elements while the other side still has many
+const C: usize = 3;
+
+// Deque made of two persistent lists, the elements are split between the front
+// list and the reversed back list. Pushes and pops work on the heads of the two
+// lists and whenever one of them gets too long the elements are split evenly
+// again by copying the whole deque.
+//
+// The copy is O(n) and an even split is at least O(n) operations away from the
+// next one, so the operations are amortized O(1) only when the deque is used
+// ephemerally, every operation applied to the newest version. This is not a
+// persistent bound: old versions stay valid, but a version one push or pop
+// short of the threshold pays the O(n) copy every time that push or pop is
+// applied to it again, see StandardOps-OldVersion in benches/standard_ops.rs.
+pub struct PersistentDeque<T, P: SharedPointerKind> {
+    front: PersistentList<T, P>,
+    front_len: usize,
+    // last element of the deque is the head of the back
+    back: PersistentList<T, P>,
+    back_len: usize,
+}
+
+impl<T, P: SharedPointerKind> PersistentDeque<T, P> {
+    pub fn new() -> Self {
+        PersistentDeque {
+            front: PersistentList::new(),
+            front_len: 0,
+            back: PersistentList::new(),
+            back_len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.front_len + self.back_len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // thanks to the balance invariant, when one of the lists is empty the other
+    // one has at most one element, which is both the first and the last one
+    pub fn peek_front(&self) -> Option<&T> {
+        self.front.head().or_else(|| self.back.head())
+    }
+
+    pub fn peek_back(&self) -> Option<&T> {
+        self.back.head().or_else(|| self.front.head())
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, P> {
+        Iter {
+            front: self.front.iter(),
+            back: self.back.iter().collect(),
+        }
+    }
+}
+
+impl<T: Clone, P: SharedPointerKind> PersistentDeque<T, P> {
+    pub fn push_front(&self, val: T) -> PersistentDeque<T, P> {
+        PersistentDeque {
+            front: self.front.prepend(val),
+            front_len: self.front_len + 1,
+            back: self.back.clone(),
+            back_len: self.back_len,
+        }
+        .balance()
+    }
+
+    pub fn push_back(&self, val: T) -> PersistentDeque<T, P> {
+        PersistentDeque {
+            front: self.front.clone(),
+            front_len: self.front_len,
+            back: self.back.prepend(val),
+            back_len: self.back_len + 1,
+        }
+        .balance()
+    }
+
+    pub fn pop_front(&self) -> PersistentDeque<T, P> {
+        if self.front.is_empty() {
+            // the only element left is in the back
+            return PersistentDeque::new();
+        }
+        PersistentDeque {
+            front: self.front.tail(),
+            front_len: self.front_len - 1,
+            back: self.back.clone(),
+            back_len: self.back_len,
+        }
+        .balance()
+    }
+
+    pub fn pop_back(&self) -> PersistentDeque<T, P> {
+        if self.back.is_empty() {
+            // the only element left is in the front
+            return PersistentDeque::new();
+        }
+        PersistentDeque {
+            front: self.front.clone(),
+            front_len: self.front_len,
+            back: self.back.tail(),
+            back_len: self.back_len - 1,
+        }
+        .balance()
+    }
+
+    fn balance(self) -> PersistentDeque<T, P> {
+        if self.front_len <= C * self.back_len + 1 && self.back_len <= C * self.front_len + 1 {
+            return self;
+        }
+
+        let elems: Vec<&T> = self.iter().collect();
+        let front_len = elems.len() / 2;
+        let (front_half, back_half) = elems.split_at(front_len);
+
+        let mut front = PersistentList::new();
+        for val in front_half.iter().rev() {
+            front = front.prepend((*val).clone());
+        }
+        let mut back = PersistentList::new();
+        for val in back_half {
+            back = back.prepend((*val).clone());
+        }
+
+        PersistentDeque {
+            front,
+            front_len,
+            back,
+            back_len: elems.len() - front_len,
+        }
+    }
+}
+
+impl<T, P: SharedPointerKind> Clone for PersistentDeque<T, P> {
+    fn clone(&self) -> Self {
+        PersistentDeque {
+            front: self.front.clone(),
+            front_len: self.front_len,
+            back: self.back.clone(),
+            back_len: self.back_len,
+        }
+    }
+}
+
+impl<T, P: SharedPointerKind> Default for PersistentDeque<T, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Iter<'a, T, P: SharedPointerKind> {
+    front: persistent_list::Iter<'a, T, P>,
+    // the back list is stored last element first, so the references are
+    // collected up front and popped from the end
+    back: Vec<&'a T>,
+}
+
+impl<'a, T, P: SharedPointerKind> Iterator for Iter<'a, T, P> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.front.next().or_else(|| self.back.pop())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PersistentDeque, C};
+    use crate::persistent_list::{ArcKind, RcKind};
+
+    #[test]
+    fn basics() {
+        let d = PersistentDeque::<_, RcKind>::new();
+        assert!(d.is_empty());
+        assert_eq!(d.peek_front(), None);
+        assert_eq!(d.peek_back(), None);
+
+        let d = d.push_front(2).push_front(1).push_back(3).push_back(4);
+        assert_eq!(d.len(), 4);
+        assert_eq!(d.peek_front(), Some(&1));
+        assert_eq!(d.peek_back(), Some(&4));
+        assert_eq!(d.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+
+        let d = d.pop_front().pop_back();
+        assert_eq!(d.iter().copied().collect::<Vec<_>>(), vec![2, 3]);
+
+        let d = d.pop_back().pop_back();
+        assert!(d.is_empty());
+
+        let d = d.pop_front().pop_back();
+        assert!(d.is_empty());
+    }
+
+    #[test]
+    fn pop_from_other_end() {
+        // all elements are pushed to one end, so the pops from the other end
+        // have to go through the rebalancing
+        let mut d = PersistentDeque::<_, RcKind>::new();
+        for i in 0..100 {
+            d = d.push_back(i);
+        }
+        for i in 0..100 {
+            assert_eq!(d.peek_front(), Some(&i));
+            assert_eq!(d.peek_back(), Some(&99));
+            d = d.pop_front();
+        }
+        assert!(d.is_empty());
+
+        for i in 0..100 {
+            d = d.push_front(i);
+        }
+        for i in 0..100 {
+            assert_eq!(d.peek_back(), Some(&i));
+            d = d.pop_back();
+        }
+        assert!(d.is_empty());
+    }
+
+    #[test]
+    fn old_versions_stay_valid() {
+        let v1 = PersistentDeque::<_, RcKind>::new()
+            .push_back(1)
+            .push_back(2)
+            .push_back(3);
+        let v2 = v1.pop_front().push_back(4);
+        let v3 = v1.pop_back().push_front(0);
+
+        assert_eq!(v1.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(v2.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(v3.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    // every push to the same version just below the threshold redoes the
+    // rebalancing, the results have to be the same every time and the version
+    // itself stays untouched
+    #[test]
+    fn push_same_version_repeatedly() {
+        let mut d = PersistentDeque::<_, RcKind>::new();
+        let mut len = 0;
+        while len < 50 || d.back_len < C * d.front_len + 1 {
+            d = d.push_back(len);
+            len += 1;
+        }
+        for _ in 0..10 {
+            let pushed = d.push_back(len);
+            assert_eq!(pushed.front_len, pushed.len() / 2);
+            assert_eq!(
+                pushed.iter().copied().collect::<Vec<_>>(),
+                (0..=len).collect::<Vec<_>>()
+            );
+        }
+        assert_eq!(d.back_len, C * d.front_len + 1);
+        assert_eq!(
+            d.iter().copied().collect::<Vec<_>>(),
+            (0..len).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn share_between_threads() {
+        let d = PersistentDeque::<_, ArcKind>::new()
+            .push_back(1)
+            .push_back(2);
+
+        let handle = {
+            let d = d.clone();
+            std::thread::spawn(move || d.push_front(0).pop_back())
+        };
+
+        let other = handle.join().unwrap();
+        assert_eq!(other.iter().copied().collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(d.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+    }
+}
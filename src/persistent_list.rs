@@ -0,0 +1,808 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::rc::Rc;
+
+// loom's Arc lets the model checker explore every interleaving of the atomic
+// refcount operations, see the loom tests in immutable_thread_safe_ll.rs
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(not(loom))]
+use std::sync::Arc;
+
+use crate::snapshot::{Edge, EdgeKind, NodeIds, NodeInfo, Root, Snapshot, SnapshotError};
+
+// Abstracts over the family of shared pointers used to link the nodes, so the
+// same persistent list can be built on top of Rc (single threaded) or Arc
+// (thread safe) and every change to the list lands in one place
+pub trait SharedPointerKind {
+    type Pointer<U>: Deref<Target = U> + Clone;
+
+    fn new<U>(val: U) -> Self::Pointer<U>;
+
+    fn try_unwrap<U>(ptr: Self::Pointer<U>) -> Result<U, Self::Pointer<U>>;
+
+    fn strong_count<U>(ptr: &Self::Pointer<U>) -> usize;
+}
+
+pub struct RcKind;
+
+impl SharedPointerKind for RcKind {
+    type Pointer<U> = Rc<U>;
+
+    fn new<U>(val: U) -> Rc<U> {
+        Rc::new(val)
+    }
+
+    fn try_unwrap<U>(ptr: Rc<U>) -> Result<U, Rc<U>> {
+        Rc::try_unwrap(ptr)
+    }
+
+    fn strong_count<U>(ptr: &Rc<U>) -> usize {
+        Rc::strong_count(ptr)
+    }
+}
+
+pub struct ArcKind;
+
+impl SharedPointerKind for ArcKind {
+    type Pointer<U> = Arc<U>;
+
+    fn new<U>(val: U) -> Arc<U> {
+        Arc::new(val)
+    }
+
+    fn try_unwrap<U>(ptr: Arc<U>) -> Result<U, Arc<U>> {
+        Arc::try_unwrap(ptr)
+    }
+
+    fn strong_count<U>(ptr: &Arc<U>) -> usize {
+        Arc::strong_count(ptr)
+    }
+}
+
+pub struct PersistentList<T, P: SharedPointerKind> {
+    head: Link<T, P>,
+}
+
+type Link<T, P> = Option<<P as SharedPointerKind>::Pointer<Node<T, P>>>;
+
+struct Node<T, P: SharedPointerKind> {
+    val: T,
+    next: Link<T, P>,
+}
+
+impl<T, P: SharedPointerKind> PersistentList<T, P> {
+    pub fn new() -> Self {
+        PersistentList { head: None }
+    }
+
+    pub fn prepend(&self, val: T) -> PersistentList<T, P> {
+        PersistentList {
+            head: Some(P::new(Node {
+                val,
+                next: self.head.clone(),
+            })),
+        }
+    }
+
+    pub fn tail(&self) -> PersistentList<T, P> {
+        PersistentList {
+            head: self.head.as_ref().and_then(|n| n.next.clone()),
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let mut cur = self.head.as_deref();
+        for _ in 0..index {
+            cur = cur.and_then(|n| n.next.as_deref());
+        }
+        cur.map(|n| &n.val)
+    }
+
+    pub fn head(&self) -> Option<&T> {
+        self.head.as_ref().map(|n| &n.val)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, P> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+}
+
+impl<T: Clone, P: SharedPointerKind> PersistentList<T, P> {
+    // nodes may be shared with other versions, so the values have to be
+    // cloned into a fresh chain of nodes
+    pub fn reverse(&self) -> PersistentList<T, P> {
+        let mut reversed = PersistentList::new();
+        for val in self.iter() {
+            reversed = reversed.prepend(val.clone());
+        }
+        reversed
+    }
+}
+
+// see snapshot.rs, every version is a root named by the caller, the nodes
+// shared by several versions appear in the snapshot once
+impl<T, P: SharedPointerKind> PersistentList<T, P> {
+    pub fn snapshot<'a, V>(
+        versions: impl IntoIterator<Item = (&'a str, &'a Self)>,
+        ids: &mut NodeIds,
+        mut val: impl FnMut(&T) -> V,
+    ) -> Snapshot<V>
+    where
+        T: 'a,
+        P: 'a,
+    {
+        let mut seen = HashSet::new();
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut roots = Vec::new();
+
+        for (name, list) in versions {
+            roots.push(Root {
+                name: String::from(name),
+                node: list.head.as_deref().map(|node| ids.id(node)),
+            });
+            // the rest of the list was already walked by a previous version
+            let mut cur = list.head.as_ref();
+            while let Some(ptr) = cur.filter(|ptr| seen.insert(&***ptr as *const Node<T, P>)) {
+                let id = ids.id(&**ptr);
+                nodes.push(NodeInfo {
+                    id,
+                    val: val(&ptr.val),
+                    refcount: P::strong_count(ptr),
+                });
+                if let Some(next) = &ptr.next {
+                    edges.push(Edge {
+                        from: id,
+                        to: ids.id(&**next),
+                        kind: EdgeKind::Next,
+                    });
+                }
+                cur = ptr.next.as_ref();
+            }
+        }
+
+        Snapshot {
+            nodes,
+            edges,
+            roots,
+        }
+    }
+
+    // single version with the root head, several versions sharing their nodes
+    // can be dumped with snapshot(..).to_json()
+    pub fn to_json(&self, fmt: impl FnMut(&T) -> String) -> String {
+        Self::snapshot([("head", self)], &mut NodeIds::new(), fmt).to_json()
+    }
+
+    // returns the versions with the names of their roots, nodes shared in the
+    // snapshot are shared by the versions, refcounts are not checked, they
+    // follow from the edges and roots
+    pub fn from_snapshot(snapshot: Snapshot<T>) -> Result<Vec<(String, Self)>, SnapshotError> {
+        let links = snapshot.links()?;
+        snapshot.no_prev(&links)?;
+        let heads = snapshot
+            .roots
+            .iter()
+            .map(|root| root.node.map(|id| links.index_of(id)).transpose())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // every node is built after the node it points to, so the nodes are
+        // ordered by walking from each of them until a node which is already
+        // ordered, running into a node of the current walk means a cycle
+        const NEW: u8 = 0;
+        const WALKED: u8 = 1;
+        const ORDERED: u8 = 2;
+        let mut state = vec![NEW; snapshot.nodes.len()];
+        let mut order = Vec::with_capacity(snapshot.nodes.len());
+        for start in 0..snapshot.nodes.len() {
+            let mut walk = Vec::new();
+            let mut cur = Some(start);
+            while let Some(i) = cur {
+                match state[i] {
+                    ORDERED => break,
+                    WALKED => return Err(SnapshotError::Cycle(snapshot.nodes[i].id)),
+                    _ => {
+                        state[i] = WALKED;
+                        walk.push(i);
+                        cur = links.next[i];
+                    }
+                }
+            }
+            for i in walk.into_iter().rev() {
+                state[i] = ORDERED;
+                order.push(i);
+            }
+        }
+
+        let mut reachable = vec![false; snapshot.nodes.len()];
+        for head in &heads {
+            let mut cur = *head;
+            while let Some(i) = cur.filter(|&i| !reachable[i]) {
+                reachable[i] = true;
+                cur = links.next[i];
+            }
+        }
+        if let Some(i) = reachable.iter().position(|r| !r) {
+            return Err(SnapshotError::UnreachableNode(snapshot.nodes[i].id));
+        }
+
+        let names: Vec<String> = snapshot.roots.iter().map(|r| r.name.clone()).collect();
+        let mut vals = snapshot.into_vals();
+        let mut built: Vec<Link<T, P>> = (0..vals.len()).map(|_| None).collect();
+        for &i in &order {
+            built[i] = Some(P::new(Node {
+                val: vals[i].take().unwrap(),
+                next: links.next[i].and_then(|next| built[next].clone()),
+            }));
+        }
+
+        let versions = names
+            .into_iter()
+            .zip(heads)
+            .map(|(name, head)| {
+                let head = head.and_then(|i| built[i].clone());
+                (name, PersistentList { head })
+            })
+            .collect();
+
+        // dropped in the reverse order of building, every node is still held
+        // by the nodes built after it, so no drop recurses down a long chain
+        for i in order.into_iter().rev() {
+            built[i].take();
+        }
+        Ok(versions)
+    }
+}
+
+// cloning a list is O(1), only the pointer to the head is cloned and the nodes
+// are shared between both lists
+impl<T, P: SharedPointerKind> Clone for PersistentList<T, P> {
+    fn clone(&self) -> Self {
+        PersistentList {
+            head: self.head.clone(),
+        }
+    }
+}
+
+impl<T, P: SharedPointerKind> Default for PersistentList<T, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Iter<'a, T, P: SharedPointerKind> {
+    next: Option<&'a Node<T, P>>,
+}
+
+impl<'a, T, P: SharedPointerKind> Iterator for Iter<'a, T, P> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // since Option<&> is copy it was just coppied and not moved
+        self.next.map(|n| {
+            self.next = n.next.as_deref();
+            &n.val
+        })
+    }
+}
+
+impl<'a, T, P: SharedPointerKind> IntoIterator for &'a PersistentList<T, P> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, P>;
+
+    fn into_iter(self) -> Iter<'a, T, P> {
+        self.iter()
+    }
+}
+
+// there is no IntoIterator for &mut, the values may be shared with other
+// versions of the list, so they can't be handed out mutably
+
+// values of nodes owned only by this list are moved out, values of nodes shared
+// with other versions are cloned, which is why T has to be Clone
+impl<T: Clone, P: SharedPointerKind> IntoIterator for PersistentList<T, P> {
+    type Item = T;
+    type IntoIter = IntoIter<T, P>;
+
+    fn into_iter(self) -> IntoIter<T, P> {
+        IntoIter {
+            list: self,
+            shared: false,
+        }
+    }
+}
+
+// holds on to the rest of the list, so the nodes which were not visited are
+// dropped iteratively by the list
+pub struct IntoIter<T, P: SharedPointerKind> {
+    list: PersistentList<T, P>,
+    // once a node is shared with another version, so are all the nodes after
+    // it, they are kept alive by that node
+    shared: bool,
+}
+
+impl<T: Clone, P: SharedPointerKind> Iterator for IntoIter<T, P> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let mut node = self.list.head.take()?;
+        if !self.shared {
+            match P::try_unwrap(node) {
+                Ok(mut node) => {
+                    self.list.head = node.next.take();
+                    return Some(node.val);
+                }
+                Err(shared) => {
+                    self.shared = true;
+                    node = shared;
+                }
+            }
+        }
+        self.list.head = node.next.clone();
+        Some(node.val.clone())
+    }
+}
+
+// The values are put in front of each other from the last one, so the list
+// keeps the order of the iterator. There is no Extend, Extend appends for the
+// other lists and std, but this list only grows at its front in O(1), appending
+// would copy every existing node and prepending would turn the meaning around.
+impl<T, P: SharedPointerKind> FromIterator<T> for PersistentList<T, P> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let vals: Vec<T> = iter.into_iter().collect();
+        let mut list = PersistentList::new();
+        for val in vals.into_iter().rev() {
+            list = list.prepend(val);
+        }
+        list
+    }
+}
+
+impl<T: fmt::Debug, P: SharedPointerKind> fmt::Debug for PersistentList<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T: PartialEq, P: SharedPointerKind> PartialEq for PersistentList<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other)
+    }
+}
+
+impl<T: Eq, P: SharedPointerKind> Eq for PersistentList<T, P> {}
+
+impl<T: PartialOrd, P: SharedPointerKind> PartialOrd for PersistentList<T, P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord, P: SharedPointerKind> Ord for PersistentList<T, P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+// length first, same as ll::LinkedList
+impl<T: Hash, P: SharedPointerKind> Hash for PersistentList<T, P> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.iter().count());
+        for val in self {
+            val.hash(state);
+        }
+    }
+}
+
+impl<T, P: SharedPointerKind> Drop for PersistentList<T, P> {
+    fn drop(&mut self) {
+        let mut head = self.head.take();
+        while let Some(node) = head {
+            if let Ok(mut node) = P::try_unwrap(node) {
+                head = node.next.take();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+pub use self::serde_impl::{GraphError, GraphNode, ListGraph};
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::fmt;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{Node, PersistentList, SharedPointerKind};
+
+    // A single list is serialized as a plain sequence of its values, so every
+    // version serialized on its own carries its own copy of the shared tail and
+    // gets its own copy of it back when deserialized
+    impl<T: Serialize, P: SharedPointerKind> Serialize for PersistentList<T, P> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self)
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>, P: SharedPointerKind> Deserialize<'de> for PersistentList<T, P> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let vals: Vec<T> = Vec::deserialize(deserializer)?;
+            Ok(vals.into_iter().collect())
+        }
+    }
+
+    // Several versions encoded together, every node is written once with the
+    // id of its next node and every version (root) is the id of its head, so
+    // the versions share the same nodes again after decoding.
+    //
+    // Nodes are numbered from the end of the lists, the next node always has a
+    // smaller id than the node pointing to it. Decoding builds the nodes in the
+    // order of their ids and a malformed graph can't sneak in a cycle.
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct ListGraph<T> {
+        pub nodes: Vec<GraphNode<T>>,
+        pub roots: Vec<Option<usize>>,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct GraphNode<T> {
+        pub val: T,
+        pub next: Option<usize>,
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum GraphError {
+        // next has to be the id of a node decoded before this one
+        InvalidNext { node: usize, next: usize },
+        InvalidRoot { root: usize, node: usize },
+    }
+
+    impl fmt::Display for GraphError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                GraphError::InvalidNext { node, next } => {
+                    write!(
+                        f,
+                        "node {} points to node {} which is not before it",
+                        node, next
+                    )
+                }
+                GraphError::InvalidRoot { root, node } => {
+                    write!(f, "root {} points to missing node {}", root, node)
+                }
+            }
+        }
+    }
+
+    impl Error for GraphError {}
+
+    impl<T, P: SharedPointerKind> PersistentList<T, P> {
+        pub fn to_graph<'a>(lists: impl IntoIterator<Item = &'a Self>) -> ListGraph<&'a T>
+        where
+            T: 'a,
+            P: 'a,
+        {
+            let mut ids: HashMap<*const Node<T, P>, usize> = HashMap::new();
+            let mut nodes = Vec::new();
+            let mut roots = Vec::new();
+
+            for list in lists {
+                // the walk stops at the first node already encoded by one of
+                // the previous versions, the rest of the list is shared
+                let mut unseen = Vec::new();
+                let mut next = None;
+                let mut cur = list.head.as_deref();
+                while let Some(node) = cur {
+                    if let Some(&id) = ids.get(&(node as *const _)) {
+                        next = Some(id);
+                        break;
+                    }
+                    unseen.push(node);
+                    cur = node.next.as_deref();
+                }
+
+                for node in unseen.into_iter().rev() {
+                    let id = nodes.len();
+                    ids.insert(node as *const _, id);
+                    nodes.push(GraphNode {
+                        val: &node.val,
+                        next,
+                    });
+                    next = Some(id);
+                }
+                roots.push(next);
+            }
+
+            ListGraph { nodes, roots }
+        }
+
+        // returns the versions in the order of the roots
+        pub fn from_graph(graph: ListGraph<T>) -> Result<Vec<Self>, GraphError> {
+            let mut built: Vec<P::Pointer<Node<T, P>>> = Vec::with_capacity(graph.nodes.len());
+            let mut invalid = None;
+            for (id, node) in graph.nodes.into_iter().enumerate() {
+                let next = match node.next {
+                    Some(next) if next < id => Some(built[next].clone()),
+                    Some(next) => {
+                        invalid = Some(GraphError::InvalidNext { node: id, next });
+                        break;
+                    }
+                    None => None,
+                };
+                built.push(P::new(Node {
+                    val: node.val,
+                    next,
+                }));
+            }
+
+            let lists = match invalid {
+                Some(err) => Err(err),
+                None => graph
+                    .roots
+                    .into_iter()
+                    .enumerate()
+                    .map(|(root, id)| match id {
+                        Some(id) => built
+                            .get(id)
+                            .map(|head| PersistentList {
+                                head: Some(head.clone()),
+                            })
+                            .ok_or(GraphError::InvalidRoot { root, node: id }),
+                        None => Ok(PersistentList::new()),
+                    })
+                    .collect(),
+            };
+
+            // every node is still pointed at by its successor in built, so
+            // dropping them from the back frees at most one node at a time,
+            // dropping the Vec as a whole could recurse down a long chain
+            while built.pop().is_some() {}
+            lists
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ArcKind, PersistentList, RcKind};
+
+    fn is_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn arc_list_is_thread_safe() {
+        is_send_sync::<PersistentList<i32, ArcKind>>();
+    }
+
+    #[test]
+    fn shared_tails() {
+        let base = PersistentList::<_, RcKind>::new().prepend(1).prepend(2);
+        let a = base.prepend(3);
+        let b = base.prepend(4);
+        drop(base);
+
+        assert_eq!(a.iter().copied().collect::<Vec<_>>(), vec![3, 2, 1]);
+        assert_eq!(b.iter().copied().collect::<Vec<_>>(), vec![4, 2, 1]);
+    }
+
+    #[test]
+    fn reverse() {
+        let list = PersistentList::<_, RcKind>::new()
+            .prepend(1)
+            .prepend(2)
+            .prepend(3);
+        let reversed = list.reverse();
+
+        assert_eq!(reversed.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![3, 2, 1]);
+        assert!(PersistentList::<i32, RcKind>::new().reverse().is_empty());
+    }
+
+    #[test]
+    fn long_list_drop() {
+        let mut list = PersistentList::<_, ArcKind>::new();
+        for i in 0..100_000 {
+            list = list.prepend(i);
+        }
+        // would overflow the stack with a recursive drop
+        drop(list);
+    }
+
+    #[test]
+    fn std_traits() {
+        let list: PersistentList<_, RcKind> = (1..=3).collect();
+        assert_eq!(format!("{:?}", list), "[1, 2, 3]");
+
+        let other = list.prepend(0).prepend(-1);
+        assert_eq!(format!("{:?}", other), "[-1, 0, 1, 2, 3]");
+        assert!(other < list);
+        assert_eq!(other.tail().tail(), list);
+        assert_eq!(
+            PersistentList::default(),
+            PersistentList::<i32, RcKind>::new()
+        );
+
+        let mut sum = 0;
+        for val in &other {
+            sum += val;
+        }
+        assert_eq!(sum, 5);
+
+        // the first two nodes are owned only by other, the rest is shared
+        let vals: Vec<_> = other.into_iter().collect();
+        assert_eq!(vals, [-1, 0, 1, 2, 3]);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn into_iter_moves_unshared_values() {
+        use std::sync::Arc;
+
+        let val = Arc::new(());
+        let shared = PersistentList::<_, RcKind>::new().prepend(val.clone());
+        let list = shared.prepend(val.clone());
+        drop(val);
+
+        let mut iter = list.into_iter();
+        // moved out of the node owned only by list
+        let moved = iter.next().unwrap();
+        assert_eq!(Arc::strong_count(&moved), 2);
+        // cloned out of the node shared with shared
+        let cloned = iter.next().unwrap();
+        assert_eq!(Arc::strong_count(&moved), 3);
+        drop((iter, moved, cloned, shared));
+    }
+
+    #[test]
+    fn into_iter_clones_only_shared_nodes() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        struct Counted(Rc<Cell<usize>>);
+
+        impl Clone for Counted {
+            fn clone(&self) -> Self {
+                self.0.set(self.0.get() + 1);
+                Counted(self.0.clone())
+            }
+        }
+
+        let clones = Rc::new(Cell::new(0));
+        let shared: PersistentList<_, RcKind> = (0..3).map(|_| Counted(clones.clone())).collect();
+        let list = shared
+            .prepend(Counted(clones.clone()))
+            .prepend(Counted(clones.clone()));
+        assert_eq!(list.into_iter().count(), 5);
+        assert_eq!(clones.get(), 3);
+
+        // nothing is shared once the other version is gone
+        let list = shared.prepend(Counted(clones.clone()));
+        drop(shared);
+        assert_eq!(list.into_iter().count(), 4);
+        assert_eq!(clones.get(), 3);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let list = PersistentList::<_, RcKind>::new().prepend(1).prepend(2);
+        let json = serde_json::to_string(&list).unwrap();
+        assert_eq!(json, "[2,1]");
+        let list: PersistentList<i32, ArcKind> = serde_json::from_str(&json).unwrap();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [2, 1]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn graph_keeps_sharing() {
+        use super::{GraphNode, ListGraph};
+
+        let base = PersistentList::<_, RcKind>::new().prepend(1).prepend(2);
+        let versions = [base.prepend(3), base.prepend(4).prepend(5), base.tail()];
+
+        let graph = PersistentList::to_graph(&versions);
+        assert_eq!(
+            graph,
+            ListGraph {
+                nodes: vec![
+                    GraphNode {
+                        val: &1,
+                        next: None
+                    },
+                    GraphNode {
+                        val: &2,
+                        next: Some(0)
+                    },
+                    GraphNode {
+                        val: &3,
+                        next: Some(1)
+                    },
+                    GraphNode {
+                        val: &4,
+                        next: Some(1)
+                    },
+                    GraphNode {
+                        val: &5,
+                        next: Some(3)
+                    },
+                ],
+                roots: vec![Some(2), Some(4), Some(0)],
+            }
+        );
+
+        let json = serde_json::to_string(&graph).unwrap();
+        let graph: ListGraph<i32> = serde_json::from_str(&json).unwrap();
+        let decoded = PersistentList::<_, ArcKind>::from_graph(graph).unwrap();
+
+        assert_eq!(decoded[0].iter().copied().collect::<Vec<_>>(), [3, 2, 1]);
+        assert_eq!(decoded[1].iter().copied().collect::<Vec<_>>(), [5, 4, 2, 1]);
+        assert_eq!(decoded[2].iter().copied().collect::<Vec<_>>(), [1]);
+        // same five nodes, nothing was duplicated
+        assert_eq!(PersistentList::to_graph(&decoded).nodes.len(), 5);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn graph_errors() {
+        use super::{GraphError, GraphNode, ListGraph};
+
+        // a node pointing to itself would make a cycle
+        let graph = ListGraph {
+            nodes: vec![GraphNode {
+                val: 1,
+                next: Some(0),
+            }],
+            roots: vec![Some(0)],
+        };
+        assert_eq!(
+            PersistentList::<_, RcKind>::from_graph(graph).unwrap_err(),
+            GraphError::InvalidNext { node: 0, next: 0 }
+        );
+
+        let graph = ListGraph {
+            nodes: vec![GraphNode { val: 1, next: None }],
+            roots: vec![None, Some(1)],
+        };
+        assert_eq!(
+            PersistentList::<_, RcKind>::from_graph(graph).unwrap_err(),
+            GraphError::InvalidRoot { root: 1, node: 1 }
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn long_graph_drop() {
+        let mut list = PersistentList::<_, RcKind>::new();
+        for i in 0..100_000 {
+            list = list.prepend(i);
+        }
+        let graph = PersistentList::to_graph([&list]);
+        let graph = super::ListGraph {
+            nodes: graph
+                .nodes
+                .into_iter()
+                .map(|n| super::GraphNode {
+                    val: *n.val,
+                    next: n.next,
+                })
+                .collect(),
+            roots: vec![Some(100_000)],
+        };
+        // the error is found after all the nodes were built
+        assert!(PersistentList::<_, RcKind>::from_graph(graph).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use crate::persistent_list::{self, PersistentList, SharedPointerKind};
+
+// Okasaki's batched queue, elements are pushed to the back list and popped from
+// the front list, when the front runs out the back is reversed into its place.
+//
+// push_back and pop_front are amortized O(1) only when the queue is used
+// ephemerally, every operation applied to the newest version, then every
+// element is reversed at most once. Old versions stay valid, but the bound does
+// not hold for them: a version whose front has one element and whose back has n
+// pays the O(n) reverse on every pop_front, no matter how many times it was
+// already popped. Getting O(1) for old versions too needs a real-time queue
+// (Hood-Melville, or Okasaki's banker's queue with a lazy reverse), see
+// StandardOps-OldVersion in benches/standard_ops.rs for the difference.
+pub struct PersistentQueue<T, P: SharedPointerKind> {
+    // front is empty only when the whole queue is empty, so the first element
+    // of the queue is always the head of the front
+    front: PersistentList<T, P>,
+    // newest element is the head of the back
+    back: PersistentList<T, P>,
+}
+
+impl<T, P: SharedPointerKind> PersistentQueue<T, P> {
+    pub fn new() -> Self {
+        PersistentQueue {
+            front: PersistentList::new(),
+            back: PersistentList::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.front.is_empty()
+    }
+
+    pub fn peek_front(&self) -> Option<&T> {
+        self.front.head()
+    }
+
+    pub fn push_back(&self, val: T) -> PersistentQueue<T, P> {
+        if self.front.is_empty() {
+            // back is empty as well, so the new element goes straight to the
+            // front to keep the invariant
+            PersistentQueue {
+                front: self.front.prepend(val),
+                back: PersistentList::new(),
+            }
+        } else {
+            PersistentQueue {
+                front: self.front.clone(),
+                back: self.back.prepend(val),
+            }
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, P> {
+        Iter {
+            front: self.front.iter(),
+            back: self.back.iter().collect(),
+        }
+    }
+}
+
+impl<T: Clone, P: SharedPointerKind> PersistentQueue<T, P> {
+    pub fn pop_front(&self) -> PersistentQueue<T, P> {
+        let front = self.front.tail();
+        if front.is_empty() {
+            PersistentQueue {
+                front: self.back.reverse(),
+                back: PersistentList::new(),
+            }
+        } else {
+            PersistentQueue {
+                front,
+                back: self.back.clone(),
+            }
+        }
+    }
+}
+
+impl<T, P: SharedPointerKind> Clone for PersistentQueue<T, P> {
+    fn clone(&self) -> Self {
+        PersistentQueue {
+            front: self.front.clone(),
+            back: self.back.clone(),
+        }
+    }
+}
+
+impl<T, P: SharedPointerKind> Default for PersistentQueue<T, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Iter<'a, T, P: SharedPointerKind> {
+    front: persistent_list::Iter<'a, T, P>,
+    // the back list is stored newest first, so to iterate it in queue order
+    // the references are collected up front and popped from the end
+    back: Vec<&'a T>,
+}
+
+impl<'a, T, P: SharedPointerKind> Iterator for Iter<'a, T, P> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.front.next().or_else(|| self.back.pop())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PersistentQueue;
+    use crate::persistent_list::{ArcKind, RcKind};
+
+    #[test]
+    fn fifo() {
+        let q = PersistentQueue::<_, RcKind>::new();
+        assert!(q.is_empty());
+        assert_eq!(q.peek_front(), None);
+
+        let q = q.push_back(1).push_back(2).push_back(3);
+        assert_eq!(q.peek_front(), Some(&1));
+
+        let q = q.pop_front();
+        assert_eq!(q.peek_front(), Some(&2));
+
+        let q = q.push_back(4);
+        assert_eq!(q.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4]);
+
+        let q = q.pop_front().pop_front().pop_front();
+        assert!(q.is_empty());
+
+        let q = q.pop_front();
+        assert!(q.is_empty());
+    }
+
+    #[test]
+    fn old_versions_stay_valid() {
+        let v1 = PersistentQueue::<_, RcKind>::new()
+            .push_back(1)
+            .push_back(2);
+        let v2 = v1.pop_front();
+        let v3 = v2.push_back(3);
+        let v4 = v1.push_back(10);
+
+        assert_eq!(v1.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(v2.iter().copied().collect::<Vec<_>>(), vec![2]);
+        assert_eq!(v3.iter().copied().collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(v4.iter().copied().collect::<Vec<_>>(), vec![1, 2, 10]);
+    }
+
+    // every pop of the same version redoes the reverse of its back, the results
+    // have to be the same every time and the version itself stays untouched
+    #[test]
+    fn pop_same_version_repeatedly() {
+        let mut q = PersistentQueue::<_, RcKind>::new();
+        for i in 0..100 {
+            q = q.push_back(i);
+        }
+        for _ in 0..10 {
+            let popped = q.pop_front();
+            assert_eq!(popped.peek_front(), Some(&1));
+            assert_eq!(
+                popped.iter().copied().collect::<Vec<_>>(),
+                (1..100).collect::<Vec<_>>()
+            );
+        }
+        assert_eq!(
+            q.iter().copied().collect::<Vec<_>>(),
+            (0..100).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn share_between_threads() {
+        let q = PersistentQueue::<_, ArcKind>::new()
+            .push_back(1)
+            .push_back(2);
+
+        let handle = {
+            let q = q.clone();
+            std::thread::spawn(move || q.pop_front().push_back(3))
+        };
+
+        let other = handle.join().unwrap();
+        assert_eq!(other.iter().copied().collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(q.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+    }
+}
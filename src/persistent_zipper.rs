@@ -0,0 +1,198 @@
+use std::fmt;
+
+use crate::persistent_list::{PersistentList, SharedPointerKind};
+
+// Huet's zipper, a persistent list with a cursor. Elements before the focus are
+// kept in reversed order in the prefix, so the neighbours of the focus are
+// always the heads of the two lists and every edit at the focus is O(1)
+pub struct PersistentZipper<T, P: SharedPointerKind> {
+    // element right before the focus is the head of the prefix
+    prefix: PersistentList<T, P>,
+    // focused element is the head of the suffix, when the suffix is empty the
+    // cursor is past the last element
+    suffix: PersistentList<T, P>,
+}
+
+impl<T, P: SharedPointerKind> PersistentZipper<T, P> {
+    pub fn new() -> Self {
+        PersistentZipper {
+            prefix: PersistentList::new(),
+            suffix: PersistentList::new(),
+        }
+    }
+
+    // focus is on the first element of the list, the list itself is shared
+    pub fn from_list(list: &PersistentList<T, P>) -> Self {
+        PersistentZipper {
+            prefix: PersistentList::new(),
+            suffix: list.clone(),
+        }
+    }
+
+    pub fn focus(&self) -> Option<&T> {
+        self.suffix.head()
+    }
+
+    pub fn is_at_start(&self) -> bool {
+        self.prefix.is_empty()
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.suffix.is_empty()
+    }
+
+    // new element takes the place of the focus and the old focus moves right
+    pub fn insert(&self, val: T) -> PersistentZipper<T, P> {
+        PersistentZipper {
+            prefix: self.prefix.clone(),
+            suffix: self.suffix.prepend(val),
+        }
+    }
+
+    pub fn replace(&self, val: T) -> Option<PersistentZipper<T, P>> {
+        self.suffix.head()?;
+        Some(PersistentZipper {
+            prefix: self.prefix.clone(),
+            suffix: self.suffix.tail().prepend(val),
+        })
+    }
+
+    // element right of the focus becomes the new focus
+    pub fn delete(&self) -> Option<PersistentZipper<T, P>> {
+        self.suffix.head()?;
+        Some(PersistentZipper {
+            prefix: self.prefix.clone(),
+            suffix: self.suffix.tail(),
+        })
+    }
+}
+
+// moving the cursor moves one element between the two lists, since the nodes
+// may be shared with other versions, the value has to be cloned
+impl<T: Clone, P: SharedPointerKind> PersistentZipper<T, P> {
+    pub fn left(&self) -> Option<PersistentZipper<T, P>> {
+        let val = self.prefix.head()?;
+        Some(PersistentZipper {
+            prefix: self.prefix.tail(),
+            suffix: self.suffix.prepend(val.clone()),
+        })
+    }
+
+    pub fn right(&self) -> Option<PersistentZipper<T, P>> {
+        let val = self.suffix.head()?;
+        Some(PersistentZipper {
+            prefix: self.prefix.prepend(val.clone()),
+            suffix: self.suffix.tail(),
+        })
+    }
+
+    // O(n) in the position of the focus, the suffix is shared with the result
+    pub fn to_list(&self) -> PersistentList<T, P> {
+        let mut list = self.suffix.clone();
+        for val in self.prefix.iter() {
+            list = list.prepend(val.clone());
+        }
+        list
+    }
+}
+
+impl<T, P: SharedPointerKind> Clone for PersistentZipper<T, P> {
+    fn clone(&self) -> Self {
+        PersistentZipper {
+            prefix: self.prefix.clone(),
+            suffix: self.suffix.clone(),
+        }
+    }
+}
+
+impl<T, P: SharedPointerKind> Default for PersistentZipper<T, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// focused element is marked with ^, e.g. [1, ^2, 3], when the cursor is past
+// the last element the mark is at the end, e.g. [1, 2, 3, ^]
+impl<T: fmt::Display, P: SharedPointerKind> fmt::Display for PersistentZipper<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix: Vec<&T> = self.prefix.iter().collect();
+        write!(f, "[")?;
+        for val in prefix.iter().rev() {
+            write!(f, "{}, ", val)?;
+        }
+        write!(f, "^")?;
+        for (i, val) in self.suffix.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", val)?;
+        }
+        write!(f, "]")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PersistentZipper;
+    use crate::persistent_list::{PersistentList, RcKind};
+
+    fn list(vals: &[i32]) -> PersistentList<i32, RcKind> {
+        let mut list = PersistentList::new();
+        for val in vals.iter().rev() {
+            list = list.prepend(*val);
+        }
+        list
+    }
+
+    #[test]
+    fn movement() {
+        let z = PersistentZipper::from_list(&list(&[1, 2, 3]));
+        assert!(z.is_at_start());
+        assert_eq!(z.focus(), Some(&1));
+        assert!(z.left().is_none());
+
+        let z = z.right().unwrap().right().unwrap();
+        assert_eq!(z.focus(), Some(&3));
+
+        let z = z.right().unwrap();
+        assert!(z.is_at_end());
+        assert_eq!(z.focus(), None);
+        assert!(z.right().is_none());
+
+        let z = z.left().unwrap();
+        assert_eq!(z.focus(), Some(&3));
+    }
+
+    #[test]
+    fn edits() {
+        let original = PersistentZipper::from_list(&list(&[1, 2, 3]))
+            .right()
+            .unwrap();
+
+        let replaced = original.replace(20).unwrap();
+        let inserted = original.insert(10);
+        let deleted = original.delete().unwrap();
+
+        assert_eq!(replaced.to_string(), "[1, ^20, 3]");
+        assert_eq!(inserted.to_string(), "[1, ^10, 2, 3]");
+        assert_eq!(deleted.to_string(), "[1, ^3]");
+        assert_eq!(original.to_string(), "[1, ^2, 3]");
+
+        let end = deleted.right().unwrap();
+        assert!(end.delete().is_none());
+        assert!(end.replace(4).is_none());
+        assert_eq!(end.insert(4).to_string(), "[1, 3, ^4]");
+    }
+
+    #[test]
+    fn to_list() {
+        let z = PersistentZipper::from_list(&list(&[1, 2, 3]))
+            .right()
+            .unwrap()
+            .insert(5);
+
+        let l = z.to_list();
+        assert_eq!(l.iter().copied().collect::<Vec<_>>(), vec![1, 5, 2, 3]);
+        assert_eq!(PersistentZipper::<i32, RcKind>::new().to_string(), "[^]");
+    }
+}
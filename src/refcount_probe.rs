@@ -0,0 +1,154 @@
+use std::fmt;
+
+#[cfg(any(test, feature = "refcount-probe"))]
+use crate::snapshot::NodeIds;
+
+// Refcounts of the nodes of a list after every step of one operation, the steps
+// are labeled with the refcount changes they are supposed to make, e.g.
+// "clone new_head into old_head.prev (+1)", so the labels in dll.rs and
+// weak_dll.rs can be checked against the real counts. Recorded by the probe_*
+// methods of the lists.
+//
+// Nodes are numbered in the order they are first seen, a node which is not
+// reachable from the list or from the locals of the operation at some step
+// (because it was not created yet, or was already freed) has no counts there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline {
+    // label of every node, its id followed by its value
+    pub nodes: Vec<String>,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub label: &'static str,
+    // by node id
+    pub counts: Vec<Option<Counts>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Counts {
+    pub strong: usize,
+    pub weak: usize,
+}
+
+impl Timeline {
+    // counts of one node at every step
+    pub fn node(&self, id: usize) -> Vec<Option<Counts>> {
+        self.steps
+            .iter()
+            .map(|step| step.counts.get(id).copied().flatten())
+            .collect()
+    }
+
+    pub fn step(&self, label: &str) -> Option<&Step> {
+        self.steps.iter().find(|step| step.label == label)
+    }
+
+    // number of increments and decrements of the counts seen between the
+    // steps, a node which is not there counts as 0/0, so allocating a node is
+    // one strong increment and freeing it one decrement. Changes made and undone
+    // between two steps (like a temporary clone) are not seen.
+    pub fn traffic(&self) -> usize {
+        let counts = |step: &Step, id: usize| {
+            step.counts
+                .get(id)
+                .copied()
+                .flatten()
+                .unwrap_or(Counts { strong: 0, weak: 0 })
+        };
+        self.steps
+            .windows(2)
+            .map(|pair| {
+                (0..self.nodes.len())
+                    .map(|id| {
+                        let (before, after) = (counts(&pair[0], id), counts(&pair[1], id));
+                        before.strong.abs_diff(after.strong) + before.weak.abs_diff(after.weak)
+                    })
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+}
+
+// one row per step and one column per node, every cell is strong/weak
+impl fmt::Display for Timeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label_width = self
+            .steps
+            .iter()
+            .map(|step| step.label.len())
+            .chain([4])
+            .max()
+            .unwrap();
+        let widths: Vec<usize> = self.nodes.iter().map(|node| node.len().max(5)).collect();
+
+        let mut header = format!("{:<1$}", "step", label_width);
+        let mut rule = "-".repeat(label_width);
+        for (node, width) in self.nodes.iter().zip(&widths) {
+            header.push_str(&format!(" | {:<1$}", node, width));
+            rule.push_str(&format!("-+-{}", "-".repeat(*width)));
+        }
+        writeln!(f, "{}", header.trim_end())?;
+        writeln!(f, "{}", rule)?;
+
+        for step in &self.steps {
+            let mut row = format!("{:<1$}", step.label, label_width);
+            for (id, width) in widths.iter().enumerate() {
+                let cell = match step.counts.get(id).copied().flatten() {
+                    Some(counts) => format!("{}/{}", counts.strong, counts.weak),
+                    None => String::new(),
+                };
+                row.push_str(&format!(" | {:<1$}", cell, width));
+            }
+            writeln!(f, "{}", row.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+// only used by the probes, which are built for the tests and with the
+// refcount-probe feature
+#[cfg(any(test, feature = "refcount-probe"))]
+pub(crate) struct Recorder {
+    ids: NodeIds,
+    timeline: Timeline,
+}
+
+#[cfg(any(test, feature = "refcount-probe"))]
+impl Recorder {
+    pub(crate) fn new() -> Self {
+        Recorder {
+            ids: NodeIds::new(),
+            timeline: Timeline {
+                nodes: Vec::new(),
+                steps: Vec::new(),
+            },
+        }
+    }
+
+    // starts a new step, the nodes reported after it are added to it
+    pub(crate) fn step(&mut self, label: &'static str) {
+        self.timeline.steps.push(Step {
+            label,
+            counts: Vec::new(),
+        });
+    }
+
+    // the value is only formatted the first time the node is seen
+    pub(crate) fn node<N>(&mut self, node: *const N, counts: Counts, val: impl FnOnce() -> String) {
+        let id = self.ids.id(node);
+        if id == self.timeline.nodes.len() {
+            self.timeline.nodes.push(format!("#{} {}", id, val()));
+        }
+        let step = self.timeline.steps.last_mut().expect("no step started");
+        if step.counts.len() <= id {
+            step.counts.resize(id + 1, None);
+        }
+        step.counts[id] = Some(counts);
+    }
+
+    pub(crate) fn finish(self) -> Timeline {
+        self.timeline
+    }
+}
@@ -0,0 +1,837 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+// Exact shape of a list at one point in time, every node with its value and
+// refcount, every link between the nodes as an edge and the pointers the list
+// (or the versions of a persistent list) holds into its nodes as named roots.
+// Snapshots are taken with the snapshot method of each list and turned back
+// into lists with from_snapshot, see ll.rs, dll.rs and persistent_list.rs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot<V> {
+    pub nodes: Vec<NodeInfo<V>>,
+    pub edges: Vec<Edge>,
+    pub roots: Vec<Root>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeInfo<V> {
+    pub id: usize,
+    pub val: V,
+    // number of owning pointers to the node, 1 for the boxed nodes of ll
+    pub refcount: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    Next,
+    Prev,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Root {
+    pub name: String,
+    pub node: Option<usize>,
+}
+
+// Hands out ids to the nodes by their address, in the order they are first
+// seen. Taking several snapshots with the same NodeIds gives a node the same id
+// in all of them, so the snapshots can be compared node by node.
+//
+// A node freed between two snapshots can have its memory reused by a new node,
+// which then inherits its id, so the ids are only stable for nodes which stay
+// alive in between.
+#[derive(Debug, Default)]
+pub struct NodeIds {
+    ids: HashMap<usize, usize>,
+}
+
+impl NodeIds {
+    pub fn new() -> Self {
+        NodeIds {
+            ids: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn id<N>(&mut self, node: *const N) -> usize {
+        let next = self.ids.len();
+        *self.ids.entry(node as usize).or_insert(next)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    // malformed binary encoding
+    BadMagic,
+    UnsupportedVersion(u16),
+    UnexpectedEnd,
+    TrailingBytes,
+    InvalidValue { node: usize },
+    InvalidEdgeKind(u8),
+    InvalidRootName,
+    // snapshot which doesn't describe a valid list
+    DuplicateNode(usize),
+    UnknownNode(usize),
+    DuplicateEdge { node: usize, kind: EdgeKind },
+    MissingRoot(String),
+    MismatchedRoot(String),
+    MismatchedPrev(usize),
+    Cycle(usize),
+    UnreachableNode(usize),
+    // snapshot which doesn't fit the binary encoding
+    RootNameTooLong(String),
+    TooBig(usize),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::BadMagic => write!(f, "not a list snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            SnapshotError::UnexpectedEnd => write!(f, "snapshot ends unexpectedly"),
+            SnapshotError::TrailingBytes => write!(f, "trailing bytes after the snapshot"),
+            SnapshotError::InvalidValue { node } => write!(f, "invalid value of node {}", node),
+            SnapshotError::InvalidEdgeKind(kind) => write!(f, "invalid edge kind {}", kind),
+            SnapshotError::InvalidRootName => write!(f, "root name is not valid utf-8"),
+            SnapshotError::DuplicateNode(node) => write!(f, "node {} appears twice", node),
+            SnapshotError::UnknownNode(node) => write!(f, "unknown node {}", node),
+            SnapshotError::DuplicateEdge { node, kind } => {
+                write!(f, "node {} has more than one {:?} edge", node, kind)
+            }
+            SnapshotError::MissingRoot(name) => write!(f, "missing root {}", name),
+            SnapshotError::MismatchedRoot(name) => {
+                write!(f, "root {} doesn't point where the list ends", name)
+            }
+            SnapshotError::MismatchedPrev(node) => {
+                write!(f, "prev edge of node {} doesn't match the next edges", node)
+            }
+            SnapshotError::Cycle(node) => write!(f, "next edges loop back to node {}", node),
+            SnapshotError::UnreachableNode(node) => {
+                write!(f, "node {} can't be reached from any root", node)
+            }
+            SnapshotError::RootNameTooLong(name) => {
+                write!(f, "root name of {} bytes is too long", name.len())
+            }
+            SnapshotError::TooBig(n) => write!(f, "{} doesn't fit the 32 bits of the encoding", n),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+// Links of the snapshot resolved to indices into nodes, every edge checked to
+// connect two known nodes and every node to have at most one edge of a kind
+pub(crate) struct Links {
+    pub(crate) next: Vec<Option<usize>>,
+    pub(crate) prev: Vec<Option<usize>>,
+    index: HashMap<usize, usize>,
+}
+
+impl Links {
+    pub(crate) fn index_of(&self, id: usize) -> Result<usize, SnapshotError> {
+        self.index
+            .get(&id)
+            .copied()
+            .ok_or(SnapshotError::UnknownNode(id))
+    }
+}
+
+impl<V> Snapshot<V> {
+    pub(crate) fn links(&self) -> Result<Links, SnapshotError> {
+        let mut index = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if index.insert(node.id, i).is_some() {
+                return Err(SnapshotError::DuplicateNode(node.id));
+            }
+        }
+
+        let mut next = vec![None; self.nodes.len()];
+        let mut prev = vec![None; self.nodes.len()];
+        for edge in &self.edges {
+            let (from, to) = match (index.get(&edge.from), index.get(&edge.to)) {
+                (Some(&from), Some(&to)) => (from, to),
+                (None, _) => return Err(SnapshotError::UnknownNode(edge.from)),
+                (_, None) => return Err(SnapshotError::UnknownNode(edge.to)),
+            };
+            let link = match edge.kind {
+                EdgeKind::Next => &mut next[from],
+                EdgeKind::Prev => &mut prev[from],
+            };
+            if link.replace(to).is_some() {
+                return Err(SnapshotError::DuplicateEdge {
+                    node: edge.from,
+                    kind: edge.kind,
+                });
+            }
+        }
+
+        Ok(Links { next, prev, index })
+    }
+
+    // index of the node the root points to
+    pub(crate) fn root(&self, links: &Links, name: &str) -> Result<Option<usize>, SnapshotError> {
+        let root = self
+            .roots
+            .iter()
+            .find(|root| root.name == name)
+            .ok_or_else(|| SnapshotError::MissingRoot(name.to_string()))?;
+        root.node.map(|id| links.index_of(id)).transpose()
+    }
+
+    // fails with the first prev edge, for lists which have none
+    pub(crate) fn no_prev(&self, links: &Links) -> Result<(), SnapshotError> {
+        match links.prev.iter().position(Option::is_some) {
+            Some(i) => Err(SnapshotError::MismatchedPrev(self.nodes[i].id)),
+            None => Ok(()),
+        }
+    }
+
+    // values by the index of their node, so they can be moved out in any order
+    pub(crate) fn into_vals(self) -> Vec<Option<V>> {
+        self.nodes.into_iter().map(|node| Some(node.val)).collect()
+    }
+
+    // indices of the nodes of a list which has its head at the root and owns
+    // all the nodes of the snapshot, in the order of the next edges
+    pub(crate) fn chain(
+        &self,
+        links: &Links,
+        head: Option<usize>,
+    ) -> Result<Vec<usize>, SnapshotError> {
+        let mut chain = Vec::new();
+        let mut seen = HashSet::new();
+        let mut cur = head;
+        while let Some(i) = cur {
+            if !seen.insert(i) {
+                return Err(SnapshotError::Cycle(self.nodes[i].id));
+            }
+            chain.push(i);
+            cur = links.next[i];
+        }
+        match (0..self.nodes.len()).find(|i| !seen.contains(i)) {
+            Some(i) => Err(SnapshotError::UnreachableNode(self.nodes[i].id)),
+            None => Ok(chain),
+        }
+    }
+}
+
+// Binary encoding of a snapshot, all integers are little endian:
+//
+//   magic    b"LLSN"
+//   version  u16
+//   nodes    u32 count, then per node u32 id, u32 refcount, u32 length and the
+//            encoded value
+//   edges    u32 count, then per edge u32 from, u32 to, u8 kind (0 next, 1 prev)
+//   roots    u32 count, then per root u16 name length, the utf-8 name and u8 1
+//            followed by u32 node id, or u8 0 when the root is empty
+//
+// The version is bumped whenever the layout changes, decoding refuses versions
+// it doesn't know instead of guessing.
+const MAGIC: &[u8; 4] = b"LLSN";
+pub const VERSION: u16 = 1;
+
+// values are stored as opaque byte strings, their encoding is up to the type
+pub trait SnapshotValue: Sized {
+    fn encode(&self, out: &mut Vec<u8>);
+
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+macro_rules! snapshot_value_int {
+    ($($t:ty),*) => {$(
+        impl SnapshotValue for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn decode(bytes: &[u8]) -> Option<Self> {
+                bytes.try_into().ok().map(<$t>::from_le_bytes)
+            }
+        }
+    )*};
+}
+
+snapshot_value_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl SnapshotValue for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0] => Some(false),
+            [1] => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl SnapshotValue for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+impl<V: SnapshotValue> Snapshot<V> {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SnapshotError> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+
+        put_u32(&mut out, self.nodes.len())?;
+        let mut val = Vec::new();
+        for node in &self.nodes {
+            put_u32(&mut out, node.id)?;
+            put_u32(&mut out, node.refcount)?;
+            val.clear();
+            node.val.encode(&mut val);
+            put_u32(&mut out, val.len())?;
+            out.extend_from_slice(&val);
+        }
+
+        put_u32(&mut out, self.edges.len())?;
+        for edge in &self.edges {
+            put_u32(&mut out, edge.from)?;
+            put_u32(&mut out, edge.to)?;
+            out.push(match edge.kind {
+                EdgeKind::Next => 0,
+                EdgeKind::Prev => 1,
+            });
+        }
+
+        put_u32(&mut out, self.roots.len())?;
+        for root in &self.roots {
+            let name = u16::try_from(root.name.len())
+                .map_err(|_| SnapshotError::RootNameTooLong(root.name.clone()))?;
+            out.extend_from_slice(&name.to_le_bytes());
+            out.extend_from_slice(root.name.as_bytes());
+            match root.node {
+                Some(node) => {
+                    out.push(1);
+                    put_u32(&mut out, node)?;
+                }
+                None => out.push(0),
+            }
+        }
+        Ok(out)
+    }
+
+    // only checks the encoding, whether the snapshot describes a valid list is
+    // checked by from_snapshot of the list it is turned into
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let mut r = Reader(bytes);
+        if r.take(MAGIC.len())? != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let version = r.u16()?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let mut nodes = Vec::new();
+        for _ in 0..r.u32()? {
+            let id = r.u32()?;
+            let refcount = r.u32()?;
+            let len = r.u32()?;
+            let val = V::decode(r.take(len)?).ok_or(SnapshotError::InvalidValue { node: id })?;
+            nodes.push(NodeInfo { id, val, refcount });
+        }
+
+        let mut edges = Vec::new();
+        for _ in 0..r.u32()? {
+            let from = r.u32()?;
+            let to = r.u32()?;
+            let kind = match r.u8()? {
+                0 => EdgeKind::Next,
+                1 => EdgeKind::Prev,
+                kind => return Err(SnapshotError::InvalidEdgeKind(kind)),
+            };
+            edges.push(Edge { from, to, kind });
+        }
+
+        let mut roots = Vec::new();
+        for _ in 0..r.u32()? {
+            let len = r.u16()? as usize;
+            let name = std::str::from_utf8(r.take(len)?)
+                .map_err(|_| SnapshotError::InvalidRootName)?
+                .to_string();
+            let node = match r.u8()? {
+                0 => None,
+                _ => Some(r.u32()?),
+            };
+            roots.push(Root { name, node });
+        }
+
+        if !r.0.is_empty() {
+            return Err(SnapshotError::TrailingBytes);
+        }
+        Ok(Snapshot {
+            nodes,
+            edges,
+            roots,
+        })
+    }
+}
+
+// JSON dump for tools outside of Rust, described by schema/snapshot.schema.json.
+// Every node, edge and root is on a line of its own, so dumps taken before and
+// after an operation can be compared with a plain text diff.
+impl Snapshot<String> {
+    pub fn to_json(&self) -> String {
+        let mut out = format!("{{\n  \"version\": {},\n", VERSION);
+
+        let nodes = self.nodes.iter().map(|node| {
+            format!(
+                "{{\"id\": {}, \"value\": {}, \"refcount\": {}}}",
+                node.id,
+                json_string(&node.val),
+                node.refcount
+            )
+        });
+        json_array(&mut out, "nodes", nodes);
+        out.push_str(",\n");
+
+        let edges = self.edges.iter().map(|edge| {
+            let kind = match edge.kind {
+                EdgeKind::Next => "next",
+                EdgeKind::Prev => "prev",
+            };
+            format!(
+                "{{\"from\": {}, \"to\": {}, \"kind\": \"{}\"}}",
+                edge.from, edge.to, kind
+            )
+        });
+        json_array(&mut out, "edges", edges);
+        out.push_str(",\n");
+
+        let roots = self.roots.iter().map(|root| {
+            let node = root.node.map_or(String::from("null"), |id| id.to_string());
+            format!(
+                "{{\"name\": {}, \"node\": {}}}",
+                json_string(&root.name),
+                node
+            )
+        });
+        json_array(&mut out, "roots", roots);
+        out.push_str("\n}\n");
+        out
+    }
+}
+
+pub(crate) fn json_array(out: &mut String, name: &str, items: impl Iterator<Item = String>) {
+    out.push_str(&format!("  \"{}\": [", name));
+    let mut empty = true;
+    for item in items {
+        out.push_str(if empty { "\n    " } else { ",\n    " });
+        out.push_str(&item);
+        empty = false;
+    }
+    out.push_str(if empty { "]" } else { "\n  ]" });
+}
+
+pub(crate) fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn put_u32(out: &mut Vec<u8>, n: usize) -> Result<(), SnapshotError> {
+    let n = u32::try_from(n).map_err(|_| SnapshotError::TooBig(n))?;
+    out.extend_from_slice(&n.to_le_bytes());
+    Ok(())
+}
+
+// counts are read before anything is allocated for them, so a corrupted count
+// runs into UnexpectedEnd instead of a huge allocation
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.0.len() < n {
+            return Err(SnapshotError::UnexpectedEnd);
+        }
+        let (taken, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SnapshotError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<usize, SnapshotError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()) as usize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+    use std::{env, fs};
+
+    use super::{Edge, EdgeKind, NodeIds, NodeInfo, Root, Snapshot, SnapshotError, VERSION};
+    use crate::dll::DoublyLinkedList;
+    use crate::immutable_ll::ImmutableLinkedList;
+    use crate::ll::LinkedList;
+
+    fn sample() -> Snapshot<String> {
+        Snapshot {
+            nodes: vec![
+                NodeInfo {
+                    id: 0,
+                    val: String::from("a"),
+                    refcount: 2,
+                },
+                NodeInfo {
+                    id: 1,
+                    val: String::from("bc"),
+                    refcount: 1,
+                },
+            ],
+            edges: vec![Edge {
+                from: 0,
+                to: 1,
+                kind: EdgeKind::Next,
+            }],
+            roots: vec![
+                Root {
+                    name: String::from("head"),
+                    node: Some(0),
+                },
+                Root {
+                    name: String::from("empty"),
+                    node: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let snapshot = sample();
+        let bytes = snapshot.to_bytes().unwrap();
+        assert_eq!(&bytes[..6], b"LLSN\x01\x00");
+        assert_eq!(Snapshot::from_bytes(&bytes), Ok(snapshot));
+    }
+
+    #[test]
+    fn malformed() {
+        let bytes = sample().to_bytes().unwrap();
+        let decode = |bytes: &[u8]| Snapshot::<String>::from_bytes(bytes).unwrap_err();
+
+        assert_eq!(decode(b"LLSX\x01\x00"), SnapshotError::BadMagic);
+        assert_eq!(decode(b"LL"), SnapshotError::UnexpectedEnd);
+
+        let mut newer = bytes.clone();
+        newer[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert_eq!(
+            decode(&newer),
+            SnapshotError::UnsupportedVersion(VERSION + 1)
+        );
+
+        // every truncation is caught, none of them panics
+        for len in 0..bytes.len() {
+            assert_eq!(decode(&bytes[..len]), SnapshotError::UnexpectedEnd);
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(decode(&trailing), SnapshotError::TrailingBytes);
+
+        // kind of the only edge, right after its from and to
+        let mut kind = bytes.clone();
+        let at = 6 + 4 + (12 + 1) + (12 + 2) + 4 + 8;
+        kind[at] = 7;
+        assert_eq!(decode(&kind), SnapshotError::InvalidEdgeKind(7));
+
+        // value of the first node
+        let mut val = bytes.clone();
+        val[6 + 4 + 12] = 0xff;
+        assert_eq!(decode(&val), SnapshotError::InvalidValue { node: 0 });
+
+        let huge_count = b"LLSN\x01\x00\xff\xff\xff\xff";
+        assert_eq!(decode(huge_count), SnapshotError::UnexpectedEnd);
+    }
+
+    #[test]
+    fn too_big() {
+        let mut long_name = sample();
+        long_name.roots[0].name = "x".repeat(u16::MAX as usize + 1);
+        assert_eq!(
+            long_name.to_bytes(),
+            Err(SnapshotError::RootNameTooLong(
+                "x".repeat(u16::MAX as usize + 1)
+            ))
+        );
+
+        let mut big_id = sample();
+        big_id.nodes[0].id = u32::MAX as usize + 1;
+        assert_eq!(
+            big_id.to_bytes(),
+            Err(SnapshotError::TooBig(u32::MAX as usize + 1))
+        );
+    }
+
+    // compares the encoding with the file in tests/golden, run the tests with
+    // UPDATE_GOLDEN=1 to rewrite the files after an intended change of the format
+    fn golden(name: &str, bytes: &[u8]) -> Vec<u8> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(name);
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&path, bytes).unwrap();
+        }
+        let golden = fs::read(&path).unwrap();
+        assert_eq!(golden, bytes, "encoding of {} changed", name);
+        golden
+    }
+
+    #[test]
+    fn golden_ll() {
+        let ll: LinkedList<i32> = (1..=3).collect();
+        let snapshot = ll.snapshot(&mut NodeIds::new(), |v| *v);
+        assert_eq!(snapshot.roots[1].node, Some(2));
+
+        let bytes = golden("ll.snap", &snapshot.to_bytes().unwrap());
+        let decoded = LinkedList::from_snapshot(Snapshot::from_bytes(&bytes).unwrap()).unwrap();
+        assert_eq!(decoded, ll);
+    }
+
+    #[test]
+    fn golden_dll() {
+        let dll: DoublyLinkedList<i32> = (1..=3).collect();
+        let snapshot = dll.snapshot(&mut NodeIds::new(), |v| *v);
+        // every node is pointed at by two others, or by the list at the ends
+        assert!(snapshot.nodes.iter().all(|node| node.refcount == 2));
+        assert_eq!(snapshot.edges.len(), 4);
+
+        let bytes = golden("dll.snap", &snapshot.to_bytes().unwrap());
+        let decoded =
+            DoublyLinkedList::from_snapshot(Snapshot::from_bytes(&bytes).unwrap()).unwrap();
+        assert_eq!(decoded.snapshot(&mut NodeIds::new(), |v| *v), snapshot);
+    }
+
+    #[test]
+    fn golden_persistent() {
+        let base: ImmutableLinkedList<String> = ["b", "a"].iter().map(|s| s.to_string()).collect();
+        let versions = [
+            ("left", base.prepend(String::from("l"))),
+            ("right", base.prepend(String::from("r"))),
+            ("base", base.clone()),
+            ("empty", ImmutableLinkedList::new()),
+        ];
+        drop(base);
+        let snapshot = ImmutableLinkedList::snapshot(
+            versions.iter().map(|(name, list)| (*name, list)),
+            &mut NodeIds::new(),
+            String::clone,
+        );
+        // shared tail is only there once
+        assert_eq!(snapshot.nodes.len(), 4);
+
+        let bytes = golden("persistent.snap", &snapshot.to_bytes().unwrap());
+        let decoded =
+            ImmutableLinkedList::from_snapshot(Snapshot::from_bytes(&bytes).unwrap()).unwrap();
+        // same nodes, refcounts and sharing
+        let resnapshot = ImmutableLinkedList::snapshot(
+            decoded.iter().map(|(name, list)| (name.as_str(), list)),
+            &mut NodeIds::new(),
+            String::clone,
+        );
+        assert_eq!(resnapshot, snapshot);
+    }
+
+    #[test]
+    fn invalid_lists() {
+        let dll: DoublyLinkedList<i32> = (1..=3).collect();
+        let snapshot = dll.snapshot(&mut NodeIds::new(), |v| *v);
+
+        let mut bad = snapshot.clone();
+        bad.edges
+            .retain(|e| !(e.from == 2 && e.kind == EdgeKind::Prev));
+        assert_eq!(
+            DoublyLinkedList::from_snapshot(bad).unwrap_err(),
+            SnapshotError::MismatchedPrev(2)
+        );
+
+        let mut bad = snapshot.clone();
+        bad.roots[1].node = Some(1);
+        assert_eq!(
+            DoublyLinkedList::from_snapshot(bad).unwrap_err(),
+            SnapshotError::MismatchedRoot(String::from("tail"))
+        );
+
+        let mut bad = snapshot.clone();
+        bad.roots[0].node = Some(1);
+        assert_eq!(
+            DoublyLinkedList::from_snapshot(bad).unwrap_err(),
+            SnapshotError::UnreachableNode(0)
+        );
+
+        // ll has no prev links
+        assert_eq!(
+            LinkedList::from_snapshot(snapshot.clone()).unwrap_err(),
+            SnapshotError::MismatchedPrev(1)
+        );
+
+        let mut bad = snapshot.clone();
+        bad.roots.pop();
+        assert_eq!(
+            DoublyLinkedList::from_snapshot(bad).unwrap_err(),
+            SnapshotError::MissingRoot(String::from("tail"))
+        );
+
+        let mut bad = snapshot.clone();
+        bad.edges[0].to = 7;
+        assert_eq!(
+            DoublyLinkedList::from_snapshot(bad).unwrap_err(),
+            SnapshotError::UnknownNode(7)
+        );
+
+        let mut bad = snapshot;
+        bad.edges.push(Edge {
+            from: 0,
+            to: 2,
+            kind: EdgeKind::Next,
+        });
+        assert_eq!(
+            DoublyLinkedList::from_snapshot(bad).unwrap_err(),
+            SnapshotError::DuplicateEdge {
+                node: 0,
+                kind: EdgeKind::Next
+            }
+        );
+
+        // persistent nodes can't form a cycle
+        let cycle = Snapshot {
+            nodes: (0..2)
+                .map(|id| NodeInfo {
+                    id,
+                    val: 0,
+                    refcount: 1,
+                })
+                .collect(),
+            edges: vec![
+                Edge {
+                    from: 0,
+                    to: 1,
+                    kind: EdgeKind::Next,
+                },
+                Edge {
+                    from: 1,
+                    to: 0,
+                    kind: EdgeKind::Next,
+                },
+            ],
+            roots: vec![Root {
+                name: String::from("v"),
+                node: Some(0),
+            }],
+        };
+        assert_eq!(
+            ImmutableLinkedList::from_snapshot(cycle).unwrap_err(),
+            SnapshotError::Cycle(0)
+        );
+    }
+
+    #[test]
+    fn json() {
+        let dll: DoublyLinkedList<i32> = (1..=2).collect();
+        assert_eq!(
+            dll.to_json(|v| v.to_string()),
+            r#"{
+  "version": 1,
+  "nodes": [
+    {"id": 0, "value": "1", "refcount": 2},
+    {"id": 1, "value": "2", "refcount": 2}
+  ],
+  "edges": [
+    {"from": 0, "to": 1, "kind": "next"},
+    {"from": 1, "to": 0, "kind": "prev"}
+  ],
+  "roots": [
+    {"name": "head", "node": 0},
+    {"name": "tail", "node": 1}
+  ]
+}
+"#
+        );
+
+        let ll = LinkedList::<i32>::new();
+        let json = ll.to_json(|v| v.to_string());
+        assert!(json.contains(r#""nodes": [],"#));
+        assert!(json.contains(r#"{"name": "tail", "node": null}"#));
+
+        let list = ImmutableLinkedList::new().prepend("say \"hi\"\n\u{1}");
+        let json: serde_json::Value =
+            serde_json::from_str(&list.to_json(|v| v.to_string())).unwrap();
+        assert_eq!(json["nodes"][0]["value"], "say \"hi\"\n\u{1}");
+        assert_eq!(json["roots"][0]["name"], "head");
+    }
+
+    // the schema can't be checked without a validator, but at least every key
+    // it requires has to be in the dump and nothing more
+    #[test]
+    fn json_matches_schema() {
+        use serde_json::Value;
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("schema/snapshot.schema.json");
+        let schema: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        let dll: DoublyLinkedList<i32> = (1..=2).collect();
+        let dump: Value = serde_json::from_str(&dll.to_json(|v| v.to_string())).unwrap();
+
+        let keys = |obj: &Value| {
+            let mut keys: Vec<_> = obj.as_object().unwrap().keys().cloned().collect();
+            keys.sort();
+            keys
+        };
+        let required = |schema: &Value| {
+            let mut keys: Vec<_> = schema["required"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|key| key.as_str().unwrap().to_string())
+                .collect();
+            keys.sort();
+            keys
+        };
+
+        assert_eq!(keys(&dump), required(&schema));
+        assert_eq!(dump["version"], schema["properties"]["version"]["const"]);
+        for array in ["nodes", "edges", "roots"] {
+            let items = &schema["properties"][array]["items"];
+            for item in dump[array].as_array().unwrap() {
+                assert_eq!(keys(item), required(items));
+            }
+        }
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+
+use crate::snapshot::{json_array, json_string, Edge, EdgeKind, NodeInfo, Snapshot};
+
+// What changed between two snapshots of the same list (or the same set of
+// persistent versions). Nodes are matched by their ids, so both snapshots have
+// to be taken with the same NodeIds, see snapshot.rs.
+//
+// Refcount changes are reported for the nodes present in both snapshots, they
+// are the change totals in the comments of push and pop in dll.rs, e.g. after
+// push_front the old head has a new prev edge, lost the head root and its
+// refcount changed by 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotDiff<V> {
+    pub added: Vec<AddedNode<V>>,
+    pub removed: Vec<NodeInfo<V>>,
+    pub relinked: Vec<Relink>,
+    pub refcounts: Vec<RefcountChange>,
+    pub roots: Vec<RootMove>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddedNode<V> {
+    pub node: NodeInfo<V>,
+    pub next: Option<usize>,
+    pub prev: Option<usize>,
+}
+
+// link of a node present in both snapshots which points somewhere else
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Relink {
+    pub node: usize,
+    pub kind: EdgeKind,
+    pub before: Option<usize>,
+    pub after: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefcountChange {
+    pub node: usize,
+    pub before: usize,
+    pub after: usize,
+}
+
+// a root missing from one of the snapshots counts as empty there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootMove {
+    pub name: String,
+    pub before: Option<usize>,
+    pub after: Option<usize>,
+}
+
+type LinkMap = HashMap<(usize, EdgeKind), usize>;
+
+fn link_map(edges: &[Edge]) -> LinkMap {
+    edges
+        .iter()
+        .map(|edge| ((edge.from, edge.kind), edge.to))
+        .collect()
+}
+
+impl<V: Clone> SnapshotDiff<V> {
+    pub fn new(before: &Snapshot<V>, after: &Snapshot<V>) -> Self {
+        let old: HashMap<_, _> = before.nodes.iter().map(|node| (node.id, node)).collect();
+        let new: HashMap<_, _> = after.nodes.iter().map(|node| (node.id, node)).collect();
+        let old_links = link_map(&before.edges);
+        let new_links = link_map(&after.edges);
+
+        let mut diff = SnapshotDiff {
+            added: Vec::new(),
+            removed: Vec::new(),
+            relinked: Vec::new(),
+            refcounts: Vec::new(),
+            roots: Vec::new(),
+        };
+
+        for node in &after.nodes {
+            let Some(old_node) = old.get(&node.id) else {
+                diff.added.push(AddedNode {
+                    node: node.clone(),
+                    next: new_links.get(&(node.id, EdgeKind::Next)).copied(),
+                    prev: new_links.get(&(node.id, EdgeKind::Prev)).copied(),
+                });
+                continue;
+            };
+            for kind in [EdgeKind::Next, EdgeKind::Prev] {
+                let before = old_links.get(&(node.id, kind)).copied();
+                let after = new_links.get(&(node.id, kind)).copied();
+                if before != after {
+                    diff.relinked.push(Relink {
+                        node: node.id,
+                        kind,
+                        before,
+                        after,
+                    });
+                }
+            }
+            if old_node.refcount != node.refcount {
+                diff.refcounts.push(RefcountChange {
+                    node: node.id,
+                    before: old_node.refcount,
+                    after: node.refcount,
+                });
+            }
+        }
+
+        diff.removed = before
+            .nodes
+            .iter()
+            .filter(|node| !new.contains_key(&node.id))
+            .cloned()
+            .collect();
+
+        // in the order of the roots before, then the roots which are new
+        let old_roots: HashMap<_, _> = before.roots.iter().map(|r| (&r.name, r.node)).collect();
+        let new_roots: HashMap<_, _> = after.roots.iter().map(|r| (&r.name, r.node)).collect();
+        let names = before.roots.iter().chain(
+            after
+                .roots
+                .iter()
+                .filter(|root| !old_roots.contains_key(&root.name)),
+        );
+        for root in names {
+            let before = old_roots.get(&root.name).copied().flatten();
+            let after = new_roots.get(&root.name).copied().flatten();
+            if before != after {
+                diff.roots.push(RootMove {
+                    name: root.name.clone(),
+                    before,
+                    after,
+                });
+            }
+        }
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.relinked.is_empty()
+            && self.refcounts.is_empty()
+            && self.roots.is_empty()
+    }
+}
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+fn node_ref(node: Option<usize>) -> String {
+    node.map_or(String::from("none"), |id| format!("#{}", id))
+}
+
+fn kind_name(kind: EdgeKind) -> &'static str {
+    match kind {
+        EdgeKind::Next => "next",
+        EdgeKind::Prev => "prev",
+    }
+}
+
+impl SnapshotDiff<String> {
+    // one line per change, + added node, - removed node, ~ relinked node or
+    // changed refcount, @ moved root. With color the lines are colored with
+    // ANSI escapes the same way, for printing to a terminal.
+    pub fn report(&self, color: bool) -> String {
+        let mut out = String::new();
+        let mut line = |paint: &str, text: String| {
+            if color {
+                out.push_str(&format!("{}{}{}\n", paint, text, RESET));
+            } else {
+                out.push_str(&text);
+                out.push('\n');
+            }
+        };
+
+        for added in &self.added {
+            let mut text = format!(
+                "+ #{} {:?} refcount {}",
+                added.node.id, added.node.val, added.node.refcount
+            );
+            if let Some(next) = added.next {
+                text.push_str(&format!(", next #{}", next));
+            }
+            if let Some(prev) = added.prev {
+                text.push_str(&format!(", prev #{}", prev));
+            }
+            line(GREEN, text);
+        }
+        for node in &self.removed {
+            line(RED, format!("- #{} {:?}", node.id, node.val));
+        }
+        for relink in &self.relinked {
+            line(
+                YELLOW,
+                format!(
+                    "~ #{} {} {} -> {}",
+                    relink.node,
+                    kind_name(relink.kind),
+                    node_ref(relink.before),
+                    node_ref(relink.after)
+                ),
+            );
+        }
+        for change in &self.refcounts {
+            line(
+                YELLOW,
+                format!(
+                    "~ #{} refcount {} -> {} ({:+})",
+                    change.node,
+                    change.before,
+                    change.after,
+                    change.after as isize - change.before as isize
+                ),
+            );
+        }
+        for root in &self.roots {
+            line(
+                CYAN,
+                format!(
+                    "@ {} {} -> {}",
+                    root.name,
+                    node_ref(root.before),
+                    node_ref(root.after)
+                ),
+            );
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let id = |node: Option<usize>| node.map_or(String::from("null"), |id| id.to_string());
+        let mut out = String::from("{\n");
+
+        let added = self.added.iter().map(|added| {
+            format!(
+                "{{\"id\": {}, \"value\": {}, \"refcount\": {}, \"next\": {}, \"prev\": {}}}",
+                added.node.id,
+                json_string(&added.node.val),
+                added.node.refcount,
+                id(added.next),
+                id(added.prev)
+            )
+        });
+        json_array(&mut out, "added", added);
+        out.push_str(",\n");
+
+        let removed = self.removed.iter().map(|node| {
+            format!(
+                "{{\"id\": {}, \"value\": {}, \"refcount\": {}}}",
+                node.id,
+                json_string(&node.val),
+                node.refcount
+            )
+        });
+        json_array(&mut out, "removed", removed);
+        out.push_str(",\n");
+
+        let relinked = self.relinked.iter().map(|relink| {
+            format!(
+                "{{\"node\": {}, \"kind\": \"{}\", \"before\": {}, \"after\": {}}}",
+                relink.node,
+                kind_name(relink.kind),
+                id(relink.before),
+                id(relink.after)
+            )
+        });
+        json_array(&mut out, "relinked", relinked);
+        out.push_str(",\n");
+
+        let refcounts = self.refcounts.iter().map(|change| {
+            format!(
+                "{{\"node\": {}, \"before\": {}, \"after\": {}}}",
+                change.node, change.before, change.after
+            )
+        });
+        json_array(&mut out, "refcounts", refcounts);
+        out.push_str(",\n");
+
+        let roots = self.roots.iter().map(|root| {
+            format!(
+                "{{\"name\": {}, \"before\": {}, \"after\": {}}}",
+                json_string(&root.name),
+                id(root.before),
+                id(root.after)
+            )
+        });
+        json_array(&mut out, "roots", roots);
+        out.push_str("\n}\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SnapshotDiff;
+    use crate::dll::DoublyLinkedList;
+    use crate::immutable_ll::ImmutableLinkedList;
+    use crate::snapshot::NodeIds;
+
+    #[test]
+    fn push_front() {
+        let mut dll: DoublyLinkedList<i32> = (1..=2).collect();
+        let mut ids = NodeIds::new();
+        let before = dll.snapshot(&mut ids, i32::to_string);
+        dll.push_front(0);
+        let after = dll.snapshot(&mut ids, i32::to_string);
+
+        // new_head change total = +2 | old_head change total = 0
+        let diff = SnapshotDiff::new(&before, &after);
+        assert_eq!(
+            diff.report(false),
+            "+ #2 \"0\" refcount 2, next #0\n\
+             ~ #0 prev none -> #2\n\
+             @ head #0 -> #2\n"
+        );
+        assert!(diff
+            .report(true)
+            .starts_with("\x1b[32m+ #2 \"0\" refcount 2, next #0\x1b[0m\n"));
+    }
+
+    #[test]
+    fn pop_back() {
+        let mut dll: DoublyLinkedList<i32> = (1..=3).collect();
+        let mut ids = NodeIds::new();
+        let before = dll.snapshot(&mut ids, i32::to_string);
+        dll.pop_back();
+        let after = dll.snapshot(&mut ids, i32::to_string);
+
+        let diff = SnapshotDiff::new(&before, &after);
+        assert_eq!(
+            diff.report(false),
+            "- #2 \"3\"\n\
+             ~ #1 next #2 -> none\n\
+             @ tail #2 -> #1\n"
+        );
+        assert_eq!(
+            diff.to_json(),
+            r#"{
+  "added": [],
+  "removed": [
+    {"id": 2, "value": "3", "refcount": 2}
+  ],
+  "relinked": [
+    {"node": 1, "kind": "next", "before": 2, "after": null}
+  ],
+  "refcounts": [],
+  "roots": [
+    {"name": "tail", "before": 2, "after": 1}
+  ]
+}
+"#
+        );
+
+        let unchanged = dll.snapshot(&mut ids, i32::to_string);
+        assert!(SnapshotDiff::new(&after, &unchanged).is_empty());
+    }
+
+    #[test]
+    fn persistent_versions() {
+        let mut ids = NodeIds::new();
+        let v1 = ImmutableLinkedList::new().prepend(1);
+        let before = ImmutableLinkedList::snapshot([("v1", &v1)], &mut ids, i32::to_string);
+        let v2 = v1.prepend(2);
+        let after =
+            ImmutableLinkedList::snapshot([("v1", &v1), ("v2", &v2)], &mut ids, i32::to_string);
+
+        // the tail is shared, its refcount goes up instead of being copied
+        let diff = SnapshotDiff::new(&before, &after);
+        assert_eq!(
+            diff.report(false),
+            "+ #1 \"2\" refcount 1, next #0\n\
+             ~ #0 refcount 1 -> 2 (+1)\n\
+             @ v2 none -> #1\n"
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use std::sync::{Arc, Mutex};
+
+// Thread safe counterpart of dll::DoublyLinkedList, Rc is replaced by Arc and
+// RefCell by Mutex, so the list is Send + Sync and all operations take &self.
+//
+// Both ends are guarded by one lock and the nodes are only touched while the
+// lock is held, so the node mutexes are never contended, they are there only
+// because Arc needs its content to be Sync. Pushing and popping at both ends
+// could use finer grained locks, but then an operation at the head and one at
+// the tail of a short list would have to agree on the locking order of the
+// nodes they share, which is exactly what a single lock gives for free.
+pub struct DoublyLinkedList<T> {
+    ends: Mutex<Ends<T>>,
+}
+
+struct Ends<T> {
+    head: Link<T>,
+    tail: Link<T>,
+}
+
+type Link<T> = Option<Arc<Mutex<Node<T>>>>;
+
+struct Node<T> {
+    val: T,
+    next: Link<T>,
+    prev: Link<T>,
+}
+
+impl<T> Node<T> {
+    fn new(val: T) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Node {
+            val,
+            next: None,
+            prev: None,
+        }))
+    }
+}
+
+impl<T> DoublyLinkedList<T> {
+    pub fn new() -> Self {
+        DoublyLinkedList {
+            ends: Mutex::new(Ends {
+                head: None,
+                tail: None,
+            }),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ends.lock().unwrap().head.is_none()
+    }
+
+    // refcount bookkeeping is the same as in dll::DoublyLinkedList
+    pub fn push_front(&self, val: T) {
+        let new_head = Node::new(val);
+        let mut ends = self.ends.lock().unwrap();
+        match ends.head.take() {
+            Some(old_head) => {
+                old_head.lock().unwrap().prev = Some(new_head.clone());
+                new_head.lock().unwrap().next = Some(old_head);
+                ends.head = Some(new_head);
+            }
+            None => {
+                ends.tail = Some(new_head.clone());
+                ends.head = Some(new_head);
+            }
+        }
+    }
+
+    pub fn push_back(&self, val: T) {
+        let new_tail = Node::new(val);
+        let mut ends = self.ends.lock().unwrap();
+        match ends.tail.take() {
+            Some(old_tail) => {
+                old_tail.lock().unwrap().next = Some(new_tail.clone());
+                new_tail.lock().unwrap().prev = Some(old_tail);
+                ends.tail = Some(new_tail);
+            }
+            None => {
+                ends.head = Some(new_tail.clone());
+                ends.tail = Some(new_tail);
+            }
+        }
+    }
+
+    pub fn pop_front(&self) -> Option<T> {
+        let mut ends = self.ends.lock().unwrap();
+        ends.head.take().map(|old_head| {
+            match old_head.lock().unwrap().next.take() {
+                Some(new_head) => {
+                    new_head.lock().unwrap().prev.take();
+                    ends.head = Some(new_head);
+                }
+                None => {
+                    ends.tail.take();
+                }
+            }
+            into_val(old_head)
+        })
+    }
+
+    pub fn pop_back(&self) -> Option<T> {
+        let mut ends = self.ends.lock().unwrap();
+        ends.tail.take().map(|old_tail| {
+            match old_tail.lock().unwrap().prev.take() {
+                Some(new_tail) => {
+                    new_tail.lock().unwrap().next.take();
+                    ends.tail = Some(new_tail);
+                }
+                None => {
+                    ends.head.take();
+                }
+            }
+            into_val(old_tail)
+        })
+    }
+}
+
+// the node was unlinked from both of its neighbours and from the list, so the
+// caller holds the last reference to it
+fn into_val<T>(node: Arc<Mutex<Node<T>>>) -> T {
+    Arc::try_unwrap(node)
+        .ok()
+        .unwrap()
+        .into_inner()
+        .unwrap()
+        .val
+}
+
+impl<T> Default for DoublyLinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for DoublyLinkedList<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::thread;
+
+    use super::DoublyLinkedList;
+
+    fn is_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn basics() {
+        is_send_sync::<DoublyLinkedList<i32>>();
+
+        let dll = DoublyLinkedList::new();
+        assert!(dll.is_empty());
+        assert_eq!(dll.pop_front(), None);
+        assert_eq!(dll.pop_back(), None);
+
+        dll.push_front(2);
+        dll.push_front(1);
+        dll.push_back(3);
+
+        assert_eq!(dll.pop_back(), Some(3));
+        assert_eq!(dll.pop_front(), Some(1));
+        assert_eq!(dll.pop_back(), Some(2));
+        assert!(dll.is_empty());
+    }
+
+    #[test]
+    fn concurrent_ends() {
+        const THREADS: usize = 8;
+        const N: usize = 5_000;
+
+        let dll = DoublyLinkedList::new();
+
+        let popped: Vec<Vec<usize>> = thread::scope(|s| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|t| {
+                    let dll = &dll;
+                    s.spawn(move || {
+                        let mut popped = Vec::new();
+                        for i in 0..N {
+                            // every thread works on both ends
+                            if (t + i) % 2 == 0 {
+                                dll.push_front(t * N + i);
+                            } else {
+                                dll.push_back(t * N + i);
+                            }
+                            if i % 3 == 0 {
+                                popped.extend(dll.pop_front());
+                            } else if i % 3 == 1 {
+                                popped.extend(dll.pop_back());
+                            }
+                        }
+                        popped
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        let mut seen = HashSet::new();
+        for val in popped.into_iter().flatten() {
+            assert!(seen.insert(val));
+        }
+        while let Some(val) = dll.pop_back() {
+            assert!(seen.insert(val));
+        }
+        assert_eq!(seen.len(), THREADS * N);
+    }
+}
@@ -0,0 +1,192 @@
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+use crossbeam_epoch::{self as epoch, Atomic, Owned};
+
+// Lock free stack, the head is swapped with compare and swap, so any number of
+// threads can push and pop at the same time without a lock.
+//
+// A popped node can't be freed right away, because another thread may have
+// loaded it as its head and is about to read its next pointer. Nodes are
+// therefore freed through epoch based reclamation, the node is destroyed only
+// after every thread that could have seen it has unpinned its epoch. Because a
+// node's memory can't be reused while somebody still holds a pointer to it,
+// this also rules out the ABA problem of the compare and swap.
+pub struct TreiberStack<T> {
+    head: Atomic<Node<T>>,
+}
+
+struct Node<T> {
+    // popping thread moves the value out, the reclamation only frees the node
+    val: ManuallyDrop<T>,
+    next: Atomic<Node<T>>,
+}
+
+impl<T> TreiberStack<T> {
+    pub fn new() -> Self {
+        TreiberStack {
+            head: Atomic::null(),
+        }
+    }
+
+    pub fn push(&self, val: T) {
+        let mut new = Owned::new(Node {
+            val: ManuallyDrop::new(val),
+            next: Atomic::null(),
+        });
+        let guard = epoch::pin();
+
+        loop {
+            let head = self.head.load(Relaxed, &guard);
+            new.next.store(head, Relaxed);
+
+            // Release, so the popping thread sees the initialized node
+            match self
+                .head
+                .compare_exchange(head, new, Release, Relaxed, &guard)
+            {
+                Ok(_) => return,
+                Err(err) => new = err.new,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = epoch::pin();
+
+        loop {
+            let head = self.head.load(Acquire, &guard);
+            // SAFETY: the node can't be freed while this thread is pinned
+            let node = unsafe { head.as_ref() }?;
+            let next = node.next.load(Relaxed, &guard);
+
+            if self
+                .head
+                .compare_exchange(head, next, Relaxed, Relaxed, &guard)
+                .is_ok()
+            {
+                // SAFETY: the successful swap unlinked the node, so this thread
+                // is the only one moving the value out of it, and the node is
+                // destroyed once no other thread can be reading it
+                unsafe {
+                    guard.defer_destroy(head);
+                    return Some(ManuallyDrop::into_inner(ptr::read(&node.val)));
+                }
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let guard = epoch::pin();
+        self.head.load(Acquire, &guard).is_null()
+    }
+}
+
+// A value is moved in by push and moved out by the one pop whose compare and
+// swap unlinked its node, it changes threads but is never reachable from two
+// of them at once and no reference to it is handed out. The node freed later by
+// the epoch collector no longer owns the value (ManuallyDrop). So sharing the
+// stack between threads only needs T: Send, not T: Sync.
+unsafe impl<T: Send> Send for TreiberStack<T> {}
+unsafe impl<T: Send> Sync for TreiberStack<T> {}
+
+impl<T> Default for TreiberStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for TreiberStack<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::TreiberStack;
+
+    const THREADS: usize = 8;
+    const N: usize = 10_000;
+
+    #[test]
+    fn basics() {
+        let stack = TreiberStack::new();
+        assert!(stack.is_empty());
+        assert_eq!(stack.pop(), None);
+
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!(stack.pop(), Some(2));
+        stack.push(4);
+        assert_eq!(stack.pop(), Some(4));
+        assert_eq!(stack.pop(), Some(1));
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn concurrent_push_pop() {
+        let stack = TreiberStack::new();
+
+        let popped: Vec<Vec<usize>> = thread::scope(|s| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|t| {
+                    let stack = &stack;
+                    s.spawn(move || {
+                        let mut popped = Vec::new();
+                        for i in 0..N {
+                            stack.push(t * N + i);
+                            if i % 2 == 0 {
+                                popped.extend(stack.pop());
+                            }
+                        }
+                        popped
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        let mut seen = HashSet::new();
+        for val in popped.into_iter().flatten() {
+            assert!(seen.insert(val), "{} popped twice", val);
+        }
+        while let Some(val) = stack.pop() {
+            assert!(seen.insert(val), "{} popped twice", val);
+        }
+        // nothing got lost
+        assert_eq!(seen.len(), THREADS * N);
+    }
+
+    #[test]
+    fn values_are_dropped_once() {
+        let counter = Arc::new(());
+        let stack = TreiberStack::new();
+
+        thread::scope(|s| {
+            for _ in 0..THREADS {
+                s.spawn(|| {
+                    for _ in 0..N {
+                        stack.push(counter.clone());
+                        stack.pop();
+                    }
+                    for _ in 0..10 {
+                        stack.push(counter.clone());
+                    }
+                });
+            }
+        });
+
+        assert_eq!(Arc::strong_count(&counter), 1 + THREADS * 10);
+        drop(stack);
+        assert_eq!(Arc::strong_count(&counter), 1);
+    }
+}
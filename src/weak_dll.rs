@@ -0,0 +1,507 @@
+use std::cell::RefCell;
+#[cfg(any(test, feature = "refcount-probe"))]
+use std::collections::HashSet;
+use std::rc::{Rc, Weak};
+
+#[cfg(any(test, feature = "refcount-probe"))]
+use crate::refcount_probe::{Counts, Recorder, Timeline};
+
+// Variant of dll::DoublyLinkedList where the prev links and the tail are Weak.
+// Every node is owned only by its predecessor (or the head), so the nodes form
+// a chain instead of a web of cycles and dropping the head frees all of them,
+// even when the Drop of the list never runs. dll::DoublyLinkedList needs its
+// Drop to break the cycles, a list whose fields are dropped without it (e.g.
+// after being taken apart behind mem::forget) leaks every node.
+//
+// The price is paid at the back of the list, the tail has to be upgraded to a
+// strong pointer before it can be used, which is an extra increment and
+// decrement of its strong count in push_back and pop_back, and Weak keeps the
+// allocation of a freed node alive until the last Weak to it is gone.
+//
+// Refcount traffic (increments + decrements between the steps recorded by the
+// probes, including allocating and freeing the node), pushing to a list of one
+// node and popping from a list of two:
+//
+//                 dll  weak_dll
+//   push_front     2      2
+//   pop_front      2      2
+//   push_back      2      4
+//   pop_back       2      6
+//
+// Timings against dll::DoublyLinkedList are in bench_res/bench_res.txt.
+pub struct DoublyLinkedList<T> {
+    head: Link<T>,
+    tail: WeakLink<T>,
+}
+
+type Link<T> = Option<Handle<T>>;
+
+type WeakLink<T> = Option<Weak<RefCell<Node<T>>>>;
+
+type Handle<T> = Rc<RefCell<Node<T>>>;
+
+struct Node<T> {
+    val: T,
+    next: Link<T>,
+    prev: WeakLink<T>,
+}
+
+impl<T> Node<T> {
+    fn new(val: T) -> Handle<T> {
+        Rc::new(RefCell::new(Node {
+            val,
+            next: None,
+            prev: None,
+        }))
+    }
+}
+
+impl<T> DoublyLinkedList<T> {
+    pub fn new() -> Self {
+        Self {
+            head: None,
+            tail: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    // every node is pointed at strongly by one other (the head by the list)
+    // and weakly by one other (the tail by the list)
+    pub fn push_front(&mut self, val: T) {
+        self.push_front_steps(val, |_, _, _| {});
+    }
+
+    pub fn push_back(&mut self, val: T) {
+        self.push_back_steps(val, |_, _, _| {});
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.pop_front_steps(|_, _, _| {})
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.pop_back_steps(|_, _, _| {})
+    }
+
+    // steps are reported the same way as in dll.rs, the count in the label is
+    // the strong count unless it says weak
+    fn push_front_steps(
+        &mut self,
+        val: T,
+        mut step: impl FnMut(&Self, &'static str, &[&Handle<T>]),
+    ) {
+        let new_head = Node::new(val);
+        match self.head.take() {
+            Some(old_head) => {
+                old_head.borrow_mut().prev = Some(Rc::downgrade(&new_head));
+                step(
+                    self,
+                    "downgrade new_head into old_head.prev (+1 weak)",
+                    &[&new_head, &old_head],
+                );
+                new_head.borrow_mut().next = Some(old_head);
+                step(self, "move old_head into new_head.next (0)", &[&new_head]);
+            }
+            None => {
+                self.tail = Some(Rc::downgrade(&new_head));
+                step(
+                    self,
+                    "downgrade new_head into list.tail (+1 weak)",
+                    &[&new_head],
+                );
+            }
+        }
+        self.head = Some(new_head);
+        step(self, "move new_head into list.head (0)", &[]);
+    }
+
+    fn push_back_steps(
+        &mut self,
+        val: T,
+        mut step: impl FnMut(&Self, &'static str, &[&Handle<T>]),
+    ) {
+        let new_tail = Node::new(val);
+        match self.tail.replace(Rc::downgrade(&new_tail)) {
+            Some(old_tail) => {
+                step(
+                    self,
+                    "downgrade new_tail into list.tail (+1 weak)",
+                    &[&new_tail],
+                );
+                // the old tail is owned by its predecessor (or the head)
+                let owner = old_tail.upgrade().unwrap();
+                step(
+                    self,
+                    "upgrade old_tail into owner (+1)",
+                    &[&new_tail, &owner],
+                );
+                new_tail.borrow_mut().prev = Some(old_tail);
+                step(
+                    self,
+                    "move old_tail into new_tail.prev (0 weak)",
+                    &[&new_tail, &owner],
+                );
+                owner.borrow_mut().next = Some(new_tail);
+                step(self, "move new_tail into old_tail.next (0)", &[&owner]);
+            }
+            None => {
+                step(
+                    self,
+                    "downgrade new_tail into list.tail (+1 weak)",
+                    &[&new_tail],
+                );
+                self.head = Some(new_tail);
+                step(self, "move new_tail into list.head (0)", &[]);
+            }
+        }
+    }
+
+    fn pop_front_steps(
+        &mut self,
+        mut step: impl FnMut(&Self, &'static str, &[&Handle<T>]),
+    ) -> Option<T> {
+        self.head.take().map(|old_head| {
+            step(self, "take old_head out of list.head (0)", &[&old_head]);
+            let next = old_head.borrow_mut().next.take();
+            match next {
+                Some(new_head) => {
+                    new_head.borrow_mut().prev.take();
+                    step(
+                        self,
+                        "drop old_head from new_head.prev (-1 weak)",
+                        &[&old_head, &new_head],
+                    );
+                    self.head = Some(new_head);
+                    step(self, "move new_head into list.head (0)", &[&old_head]);
+                }
+                None => {
+                    self.tail.take();
+                    step(self, "drop old_head from list.tail (-1 weak)", &[&old_head]);
+                }
+            }
+            Rc::try_unwrap(old_head).ok().unwrap().into_inner().val
+        })
+    }
+
+    fn pop_back_steps(
+        &mut self,
+        mut step: impl FnMut(&Self, &'static str, &[&Handle<T>]),
+    ) -> Option<T> {
+        let old_tail = self.tail.take()?.upgrade().unwrap();
+        step(
+            self,
+            "upgrade old_tail out of list.tail (+1, -1 weak)",
+            &[&old_tail],
+        );
+        let prev = old_tail.borrow_mut().prev.take();
+        match prev {
+            Some(new_tail) => {
+                let owner = new_tail.upgrade().unwrap();
+                step(
+                    self,
+                    "upgrade new_tail into owner (+1)",
+                    &[&old_tail, &owner],
+                );
+                owner.borrow_mut().next.take();
+                step(
+                    self,
+                    "drop old_tail from new_tail.next (-1)",
+                    &[&old_tail, &owner],
+                );
+                self.tail = Some(new_tail);
+                step(
+                    self,
+                    "move new_tail into list.tail (0 weak)",
+                    &[&old_tail, &owner],
+                );
+            }
+            None => {
+                self.head.take();
+                step(self, "drop old_tail from list.head (-1)", &[&old_tail]);
+            }
+        }
+        Some(Rc::try_unwrap(old_tail).ok().unwrap().into_inner().val)
+    }
+}
+
+// same probes as for dll::DoublyLinkedList, see refcount_probe.rs
+#[cfg(any(test, feature = "refcount-probe"))]
+impl<T> DoublyLinkedList<T> {
+    pub fn probe_push_front(&mut self, val: T, fmt: impl Fn(&T) -> String) -> Timeline {
+        self.probe(&fmt, |dll, step| dll.push_front_steps(val, step))
+    }
+
+    pub fn probe_push_back(&mut self, val: T, fmt: impl Fn(&T) -> String) -> Timeline {
+        self.probe(&fmt, |dll, step| dll.push_back_steps(val, step))
+    }
+
+    pub fn probe_pop_front(&mut self, fmt: impl Fn(&T) -> String) -> (Option<T>, Timeline) {
+        let mut popped = None;
+        let timeline = self.probe(&fmt, |dll, step| popped = dll.pop_front_steps(step));
+        (popped, timeline)
+    }
+
+    pub fn probe_pop_back(&mut self, fmt: impl Fn(&T) -> String) -> (Option<T>, Timeline) {
+        let mut popped = None;
+        let timeline = self.probe(&fmt, |dll, step| popped = dll.pop_back_steps(step));
+        (popped, timeline)
+    }
+
+    fn probe(
+        &mut self,
+        fmt: &impl Fn(&T) -> String,
+        op: impl FnOnce(&mut Self, &mut dyn FnMut(&Self, &'static str, &[&Handle<T>])),
+    ) -> Timeline {
+        let mut rec = Recorder::new();
+        self.record(&mut rec, fmt, "before", &[]);
+        op(self, &mut |dll, label, locals| {
+            dll.record(&mut rec, fmt, label, locals)
+        });
+        self.record(&mut rec, fmt, "after", &[]);
+        rec.finish()
+    }
+
+    // Same walk as in dll.rs. Upgrading a Weak would change the counts, so the
+    // nodes behind the Weak links are reached through their raw pointers, the
+    // counts can be read from the Weak itself.
+    fn record(
+        &self,
+        rec: &mut Recorder,
+        fmt: &impl Fn(&T) -> String,
+        label: &'static str,
+        locals: &[&Handle<T>],
+    ) {
+        fn strong<T>(rc: &Handle<T>) -> (*const RefCell<Node<T>>, Counts) {
+            let counts = Counts {
+                strong: Rc::strong_count(rc),
+                weak: Rc::weak_count(rc),
+            };
+            (Rc::as_ptr(rc), counts)
+        }
+        fn weak<T>(weak: &Weak<RefCell<Node<T>>>) -> Option<(*const RefCell<Node<T>>, Counts)> {
+            let counts = Counts {
+                strong: weak.strong_count(),
+                weak: weak.weak_count(),
+            };
+            (counts.strong > 0).then_some((weak.as_ptr(), counts))
+        }
+
+        rec.step(label);
+        let mut seen = HashSet::new();
+        let mut stack: Vec<_> = self.tail.iter().filter_map(weak).collect();
+        stack.extend(locals.iter().rev().map(|rc| strong(rc)));
+        stack.extend(self.head.iter().map(strong));
+        while let Some((ptr, counts)) = stack.pop() {
+            if !seen.insert(ptr) {
+                continue;
+            }
+            // SAFETY: the node is alive, its strong count is not 0, and the
+            // same reasoning as in dll.rs applies to the borrow
+            match unsafe { (*ptr).try_borrow_unguarded() } {
+                Ok(node) => {
+                    rec.node(ptr, counts, || fmt(&node.val));
+                    stack.extend(node.prev.iter().filter_map(weak));
+                    stack.extend(node.next.iter().map(strong));
+                }
+                Err(_) => rec.node(ptr, counts, || String::from("?")),
+            }
+        }
+    }
+}
+
+impl<T> Default for DoublyLinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> IntoIterator for DoublyLinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+pub struct IntoIter<T>(DoublyLinkedList<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+// not needed to free the nodes, only to free them one by one instead of
+// recursively, which could overflow the stack for long lists
+impl<T> Drop for DoublyLinkedList<T> {
+    fn drop(&mut self) {
+        let mut cur = self.head.take();
+        while let Some(node) = cur {
+            cur = node.borrow_mut().next.take();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::mem::{self, ManuallyDrop};
+    use std::ptr;
+    use std::rc::Rc;
+
+    use super::DoublyLinkedList;
+    use crate::refcount_probe::Counts;
+
+    fn from_slice(vals: &[i32]) -> DoublyLinkedList<i32> {
+        let mut dll = DoublyLinkedList::new();
+        for val in vals {
+            dll.push_back(*val);
+        }
+        dll
+    }
+
+    // walks the list from both ends, so both next and prev links are checked
+    fn assert_list(dll: DoublyLinkedList<i32>, expected: &[i32]) {
+        let mut iter = dll.into_iter();
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        for _ in 0..expected.len() / 2 {
+            front.push(iter.next().unwrap());
+        }
+        while let Some(val) = iter.next_back() {
+            back.push(val);
+        }
+        back.reverse();
+        front.extend(back);
+        assert_eq!(front, expected);
+    }
+
+    #[test]
+    fn basics() {
+        let mut dll = DoublyLinkedList::new();
+        assert!(dll.is_empty());
+        assert_eq!(dll.pop_front(), None);
+        assert_eq!(dll.pop_back(), None);
+
+        dll.push_front(2);
+        dll.push_front(1);
+        dll.push_back(3);
+        assert_eq!(dll.pop_back(), Some(3));
+        assert_eq!(dll.pop_front(), Some(1));
+        assert_eq!(dll.pop_back(), Some(2));
+        assert!(dll.is_empty());
+
+        dll.push_back(1);
+        assert_eq!(dll.pop_front(), Some(1));
+        dll.push_front(1);
+        assert_eq!(dll.pop_back(), Some(1));
+        assert!(dll.is_empty());
+
+        assert_list(from_slice(&[1, 2, 3, 4, 5]), &[1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn refcounts() {
+        let mut dll = from_slice(&[1]);
+        let timeline = dll.probe_push_back(2, i32::to_string);
+        let counts = &timeline.step("after").unwrap().counts;
+        // owned once by the head or the previous node, and seen weakly once
+        // by the next node or the tail
+        assert!(counts
+            .iter()
+            .all(|counts| *counts == Some(Counts { strong: 1, weak: 1 })));
+        // the upgrade of the old tail
+        assert_eq!(timeline.node(0)[2], Some(Counts { strong: 2, weak: 1 }));
+        // every label names the count of its first node which changes
+        assert_eq!(
+            timeline.to_string(),
+            "\
+step                                        | #0 1  | #1 2
+--------------------------------------------+-------+------
+before                                      | 1/1   |
+downgrade new_tail into list.tail (+1 weak) | 1/1   | 1/1
+upgrade old_tail into owner (+1)            | 2/1   | 1/1
+move old_tail into new_tail.prev (0 weak)   | 2/1   | 1/1
+move new_tail into old_tail.next (0)        | 2/1   | 1/1
+after                                       | 1/1   | 1/1
+"
+        );
+        assert_list(dll, &[1, 2]);
+    }
+
+    // numbers in the table at the top of the file
+    #[test]
+    fn refcount_traffic() {
+        use crate::dll;
+
+        let mut strong = dll::DoublyLinkedList::new();
+        let mut weak = DoublyLinkedList::new();
+        let traffic = |strong: &mut dll::DoublyLinkedList<i32>,
+                       weak: &mut DoublyLinkedList<i32>| {
+            strong.push_back(0);
+            weak.push_back(0);
+            [
+                (
+                    strong.probe_push_front(1, i32::to_string).traffic(),
+                    weak.probe_push_front(1, i32::to_string).traffic(),
+                ),
+                (
+                    strong.probe_pop_front(i32::to_string).1.traffic(),
+                    weak.probe_pop_front(i32::to_string).1.traffic(),
+                ),
+                (
+                    strong.probe_push_back(1, i32::to_string).traffic(),
+                    weak.probe_push_back(1, i32::to_string).traffic(),
+                ),
+                (
+                    strong.probe_pop_back(i32::to_string).1.traffic(),
+                    weak.probe_pop_back(i32::to_string).1.traffic(),
+                ),
+            ]
+        };
+        assert_eq!(
+            traffic(&mut strong, &mut weak),
+            [(2, 2), (2, 2), (2, 4), (2, 6)]
+        );
+    }
+
+    // takes the list apart without running its Drop, the way a list behind
+    // mem::forget or ManuallyDrop would be, and drops the head on its own
+    #[test]
+    fn freed_without_drop() {
+        let counter = Rc::new(());
+        let mut dll = DoublyLinkedList::new();
+        for _ in 0..3 {
+            dll.push_front(counter.clone());
+            dll.push_back(counter.clone());
+        }
+        assert_eq!(Rc::strong_count(&counter), 7);
+
+        let dll = ManuallyDrop::new(dll);
+        // SAFETY: the fields are read out once and the list is never used again
+        let (head, tail) = unsafe { (ptr::read(&dll.head), ptr::read(&dll.tail)) };
+        drop(head);
+        assert_eq!(Rc::strong_count(&counter), 1);
+        drop(tail);
+    }
+
+    #[test]
+    fn long_list_drop() {
+        let mut dll = DoublyLinkedList::new();
+        for i in 0..100_000 {
+            dll.push_back(i);
+        }
+        // would overflow the stack with a recursive drop
+        mem::drop(dll);
+    }
+}